@@ -0,0 +1,359 @@
+use serde_json::{json, Value};
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    error::Result,
+    request::{Client, Fixtures},
+    wikitext::Module,
+    Args, CacheCommand,
+};
+
+/// A week.
+pub const DEFAULT_TTL: u64 = 7 * 24 * 60 * 60;
+
+/// What a cache holds. Each kind comes from its own endpoint, so they never share a directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// Parsoid HTML from the REST API.
+    Html,
+    /// Raw wikitext.
+    Wikitext,
+    /// The wikitext of a data module of the character.
+    Module(Module),
+}
+
+impl Kind {
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Html => "html",
+            Self::Wikitext => "wikitext",
+            Self::Module(module) => module.extension(),
+        }
+    }
+}
+
+pub struct Cache {
+    kind: Kind,
+    dir: PathBuf,
+    ttl: Duration,
+    offline: bool,
+    client: Arc<Client>,
+}
+
+#[derive(Debug)]
+pub struct Entry {
+    pub c: char,
+    pub revision: u64,
+    pub fetched: u64,
+}
+
+impl Entry {
+    fn age(&self) -> Duration {
+        Duration::from_secs(now().saturating_sub(self.fetched))
+    }
+}
+
+impl Cache {
+    /// The cache of Parsoid HTML pages.
+    pub fn new(args: &Args) -> Self {
+        Self::of(args, Kind::Html)
+    }
+
+    pub fn of(args: &Args, kind: Kind) -> Self {
+        Self::with_client(args, kind, Arc::new(Client::new(args)))
+    }
+
+    /// A cache of another kind whose requests go through the same client, so both share its
+    /// rate limit.
+    pub fn sibling(&self, args: &Args, kind: Kind) -> Self {
+        Self::with_client(args, kind, Arc::clone(&self.client))
+    }
+
+    fn with_client(args: &Args, kind: Kind, client: Arc<Client>) -> Self {
+        let endpoint = match kind {
+            Kind::Html => args.endpoint.clone(),
+            Kind::Wikitext => args.wikitext_endpoint.clone(),
+            Kind::Module(module) => format!("{}{}", args.wikitext_endpoint, module.prefix()),
+        };
+        Self {
+            kind,
+            dir: cache_dir(&endpoint),
+            ttl: Duration::from_secs(args.ttl),
+            offline: args.offline,
+            client,
+        }
+    }
+
+    /// Returns the page of `c`, downloading it only if it is not cached or has expired.
+    /// Returns `None` in offline mode if the page is not cached.
+    ///
+    /// The cache is bypassed when recording or replaying fixtures so every response comes from
+    /// the same place.
+    pub fn get(&self, c: char) -> Result<Option<String>> {
+        if *self.client.fixtures() != Fixtures::None {
+            return self.fetch(c);
+        }
+
+        if let Some(entry) = self.entry(c) {
+            if self.offline || entry.age() < self.ttl {
+                if let Ok(page) = fs::read_to_string(self.page_path(&entry)) {
+                    return Ok(Some(page));
+                }
+            }
+        }
+
+        if self.offline {
+            return Ok(None);
+        }
+
+        self.fetch(c)
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Downloads the page of `c` and stores it regardless of what is cached.
+    pub fn fetch(&self, c: char) -> Result<Option<String>> {
+        let response = match self.kind {
+            Kind::Html => self.client.request(c)?,
+            Kind::Wikitext => self.client.request_wikitext(c)?,
+            Kind::Module(module) => self.client.request_module(c, module)?,
+        };
+        let Some(response) = response else {
+            return Ok(None);
+        };
+        if let Fixtures::None | Fixtures::Record(_) = self.client.fixtures() {
+            if let Err(e) = self.store(c, response.revision, &response.body) {
+                eprintln!("Failed to cache {}: {}", c, e);
+            }
+        }
+        Ok(Some(response.body))
+    }
+
+    pub fn entries(&self) -> Vec<Entry> {
+        let mut entries = vec![];
+        if let Ok(dir) = fs::read_dir(&self.dir) {
+            for file in dir.flatten() {
+                let path = file.path();
+                if path.extension().is_some_and(|ext| ext == "json") {
+                    if let Some(entry) = read_entry(&path) {
+                        entries.push(entry);
+                    }
+                }
+            }
+        }
+        entries.sort_by_key(|e| e.c);
+        entries
+    }
+
+    pub fn is_expired(&self, entry: &Entry) -> bool {
+        entry.age() >= self.ttl
+    }
+
+    fn entry(&self, c: char) -> Option<Entry> {
+        read_entry(&self.entry_path(c))
+    }
+
+    fn store(&self, c: char, revision: u64, page: &str) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+
+        let old = self.entry(c);
+        let entry = Entry {
+            c,
+            revision,
+            fetched: now(),
+        };
+        fs::write(self.page_path(&entry), page)?;
+        let meta = json!({
+            "char": c.to_string(),
+            "revision": entry.revision,
+            "fetched": entry.fetched,
+        });
+        fs::write(self.entry_path(c), meta.to_string())?;
+
+        if let Some(old) = old {
+            if old.revision != revision {
+                let _ = fs::remove_file(self.page_path(&old));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn remove(&self, entry: &Entry) -> io::Result<()> {
+        // NOTE: The entry goes first, so a page that is already gone does not leave an entry
+        // behind that fails every prune.
+        fs::remove_file(self.entry_path(entry.c))?;
+        match fs::remove_file(self.page_path(entry)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    fn entry_path(&self, c: char) -> PathBuf {
+        self.dir.join(format!("{:x}.json", c as u32))
+    }
+
+    fn page_path(&self, entry: &Entry) -> PathBuf {
+        self.dir.join(format!(
+            "{:x}-{}.{}",
+            entry.c as u32,
+            entry.revision,
+            self.kind.extension()
+        ))
+    }
+
+    fn size(&self) -> u64 {
+        fs::read_dir(&self.dir)
+            .map(|dir| {
+                dir.flatten()
+                    .filter_map(|f| f.metadata().ok())
+                    .map(|m| m.len())
+                    .sum()
+            })
+            .unwrap_or(0)
+    }
+}
+
+pub fn command(args: &Args, command: &CacheCommand) -> Result<()> {
+    let cache = Cache::new(args);
+
+    match command {
+        CacheCommand::List => {
+            for entry in cache.entries() {
+                println!(
+                    "{}\trev {}\t{}{}",
+                    entry.c,
+                    entry.revision,
+                    format_age(entry.age()),
+                    if cache.is_expired(&entry) {
+                        " (expired)"
+                    } else {
+                        ""
+                    }
+                );
+            }
+        }
+        CacheCommand::Prune { all } => {
+            let mut removed = 0;
+            for entry in cache.entries() {
+                if *all || cache.is_expired(&entry) {
+                    match cache.remove(&entry) {
+                        Ok(()) => removed += 1,
+                        Err(e) => eprintln!("Failed to remove {}: {}", entry.c, e),
+                    }
+                }
+            }
+            if !args.quiet {
+                eprintln!("Removed {} pages.", removed);
+            }
+        }
+        CacheCommand::Refresh { chars } => {
+            let targets = match chars {
+                Some(chars) => chars.chars().collect::<Vec<_>>(),
+                None => cache.entries().into_iter().map(|e| e.c).collect(),
+            };
+            for c in targets {
+                cache.fetch(c)?;
+            }
+        }
+        CacheCommand::Stats => {
+            let entries = cache.entries();
+            let expired = entries.iter().filter(|e| cache.is_expired(e)).count();
+            println!("Directory: {}", cache.dir.display());
+            println!("Pages: {}", entries.len());
+            println!("Expired: {}", expired);
+            println!("Size: {:.1} KiB", cache.size() as f64 / 1024.0);
+        }
+    }
+
+    Ok(())
+}
+
+/// Pages of each endpoint are kept in their own directory, so pages of a mirror are never served
+/// for another endpoint.
+fn cache_dir(endpoint: &str) -> PathBuf {
+    let base = env::var_os("XDG_CACHE_HOME")
+        .filter(|s| !s.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))
+        .unwrap_or_else(env::temp_dir);
+    base.join(env!("CARGO_PKG_NAME"))
+        .join("pages")
+        .join(format!("{:016x}", fnv1a(endpoint.trim_end_matches('/'))))
+}
+
+/// FNV-1a, which unlike the hasher of `std` gives the same hash on every build.
+fn fnv1a(s: &str) -> u64 {
+    s.bytes().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn read_entry(path: &Path) -> Option<Entry> {
+    let meta: Value = serde_json::from_str(&fs::read_to_string(path).ok()?).ok()?;
+    Some(Entry {
+        c: meta.get("char")?.as_str()?.chars().next()?,
+        revision: meta.get("revision")?.as_u64()?,
+        fetched: meta.get("fetched")?.as_u64()?,
+    })
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    match secs {
+        0..60 => format!("{}s ago", secs),
+        60..3600 => format!("{}m ago", secs / 60),
+        3600..86400 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86400),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::request::DEFAULT_ENDPOINT;
+    use clap::Parser;
+
+    #[test]
+    fn endpoints_have_their_own_directory() {
+        let default = cache_dir(DEFAULT_ENDPOINT);
+        assert_eq!(default, cache_dir(&format!("{}/", DEFAULT_ENDPOINT)));
+        assert_ne!(default, cache_dir("http://mirror/api/rest_v1/page/html"));
+        assert_eq!(default.parent(), cache_dir("http://mirror").parent());
+    }
+
+    #[test]
+    fn removes_entries_without_a_page() {
+        let dir = env::temp_dir().join(format!("wangyun-cache-{}", std::process::id()));
+        let cache = Cache {
+            kind: Kind::Html,
+            dir: dir.clone(),
+            ttl: Duration::from_secs(DEFAULT_TTL),
+            offline: true,
+            client: Arc::new(Client::new(&Args::parse_from(["wangyun", "字"]))),
+        };
+        cache.store('字', 1, "page").expect("failed to store");
+        let entries = cache.entries();
+        fs::remove_file(cache.page_path(&entries[0])).expect("no page");
+
+        let removed = cache.remove(&entries[0]);
+        let left = cache.entries();
+        fs::remove_dir_all(&dir).expect("failed to clean up");
+        removed.expect("failed to remove");
+        assert!(left.is_empty());
+    }
+}
@@ -0,0 +1,151 @@
+use scraper::{ElementRef, Html, Node};
+
+use crate::{normalize::normalize, table::sel};
+
+/// The senses listed under a part of speech heading.
+#[derive(Debug, Clone, Default)]
+pub struct Definitions {
+    /// The heading, e.g. `Noun`, or `Definitions` when the entry does not name one.
+    pub pos: String,
+    pub senses: Vec<Sense>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Sense {
+    pub gloss: String,
+    /// Usage labels such as `Classical` or `Cantonese`.
+    pub labels: Vec<String>,
+    pub senses: Vec<Sense>,
+}
+
+/// Reads the definition lists of a part of speech section. Examples and quotations are left out.
+pub fn fetch(pos: &str, section: &str) -> Definitions {
+    let doc = Html::parse_fragment(section);
+    let senses = doc
+        .select(&sel("ol"))
+        .filter(|ol| !ol.ancestors().filter_map(ElementRef::wrap).any(is_list))
+        .flat_map(senses)
+        .collect();
+    Definitions {
+        pos: pos.to_string(),
+        senses,
+    }
+}
+
+fn senses(ol: ElementRef) -> Vec<Sense> {
+    ol.children()
+        .filter_map(ElementRef::wrap)
+        .filter(|li| li.value().name() == "li")
+        .map(|li| {
+            let mut sense = Sense::default();
+            let mut gloss = String::new();
+            read(li, &mut gloss, &mut sense);
+            sense.gloss = normalize(&gloss);
+            sense
+        })
+        .filter(|sense| !sense.gloss.is_empty() || !sense.senses.is_empty())
+        .collect()
+}
+
+/// Appends the text of `elem` to `gloss`, and its labels and sub-senses to `sense`.
+fn read(elem: ElementRef, gloss: &mut String, sense: &mut Sense) {
+    for child in elem.children() {
+        match child.value() {
+            Node::Text(t) => gloss.push_str(t),
+            Node::Element(e) if e.name() == "br" => gloss.push(' '),
+            Node::Element(e) if e.classes().any(|c| c == "usage-label-sense") => {
+                if let Some(child) = ElementRef::wrap(child) {
+                    sense.labels.extend(labels(child));
+                }
+            }
+            Node::Element(_) => {
+                let Some(child) = ElementRef::wrap(child) else {
+                    continue;
+                };
+                match child.value().name() {
+                    "ol" => sense.senses.extend(senses(child)),
+                    // NOTE: Examples are in `<dl>` and quotations in `<ul>`.
+                    "ul" | "dl" => {}
+                    _ => read(child, gloss, sense),
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// `(Classical, Cantonese)` becomes `Classical` and `Cantonese`.
+fn labels(span: ElementRef) -> Vec<String> {
+    span.select(&sel(".ib-content"))
+        .flat_map(|content| {
+            content
+                .text()
+                .collect::<String>()
+                .split(',')
+                .map(normalize)
+                .collect::<Vec<_>>()
+        })
+        .filter(|label| !label.is_empty())
+        .collect()
+}
+
+/// Whether `elem` is a list item or a list of examples or sub-senses.
+fn is_list(elem: ElementRef) -> bool {
+    matches!(elem.value().name(), "li" | "ol" | "ul" | "dl")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        sections::Tree,
+        utils::{fixture, isolate_chinese_section},
+    };
+
+    fn definitions(c: char) -> Vec<Definitions> {
+        let page = fixture(c);
+        let tree = Tree::html(isolate_chinese_section(&page).expect("no Chinese"));
+        tree.pronunciations()
+            .into_iter()
+            .flat_map(|p| tree.definitions(p))
+            .map(|pos| fetch(&pos.title, tree.body(pos)))
+            .collect()
+    }
+
+    fn glosses(senses: &[Sense]) -> Vec<&str> {
+        senses.iter().map(|s| s.gloss.as_str()).collect()
+    }
+
+    #[test]
+    fn reads_senses_and_labels() {
+        let definitions = definitions('字');
+        assert_eq!(definitions.len(), 1);
+        assert_eq!(definitions[0].pos, "Definitions");
+        let senses = &definitions[0].senses;
+        assert_eq!(
+            glosses(senses),
+            ["letter; symbol; character", "word", "five minutes"]
+        );
+        assert!(senses[0].labels.is_empty());
+        assert_eq!(senses[2].labels, ["Cantonese"]);
+    }
+
+    #[test]
+    fn reads_sub_senses_without_examples() {
+        let definitions = definitions('行');
+        let pos = definitions
+            .iter()
+            .map(|d| d.pos.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(pos, ["Verb", "Adjective", "Noun"]);
+
+        let verb = &definitions[0].senses;
+        assert_eq!(glosses(verb), ["to walk; to go", "to do; to perform"]);
+        assert_eq!(verb[0].labels, ["Classical", "Cantonese"]);
+        assert_eq!(
+            glosses(&verb[0].senses),
+            ["to travel", "to leave & move on"]
+        );
+        assert!(verb[1].senses.is_empty());
+    }
+}
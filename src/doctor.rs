@@ -0,0 +1,318 @@
+use std::collections::HashSet;
+
+use crate::{
+    cache::Cache,
+    definitions,
+    error::{Error, Result},
+    etymology, japanese, korean, middle, modern,
+    normalize::html_text,
+    old_bs, old_zh,
+    regexes::regexes,
+    sections::Tree,
+    source::Page,
+    translingual,
+    utils::{isolate_chinese_section, isolate_section},
+    vietnamese, Args,
+};
+
+/// A character whose entry is known to have what its checks look for.
+struct Canary {
+    c: char,
+    /// Why it was picked.
+    note: &'static str,
+    /// Only points to its traditional form.
+    redirect: bool,
+    /// The least number of pronunciation sections.
+    pronunciations: usize,
+    middle: bool,
+    old: bool,
+}
+
+const CANARIES: &[Canary] = &[
+    Canary {
+        c: '字',
+        note: "Middle Chinese and both Old Chinese systems",
+        redirect: false,
+        pronunciations: 1,
+        middle: true,
+        old: true,
+    },
+    Canary {
+        c: '行',
+        note: "several pronunciations",
+        redirect: false,
+        pronunciations: 2,
+        middle: true,
+        old: true,
+    },
+    Canary {
+        c: '你',
+        note: "Middle Chinese only",
+        redirect: false,
+        pronunciations: 1,
+        middle: true,
+        old: false,
+    },
+    Canary {
+        c: '人',
+        note: "most modern varieties",
+        redirect: false,
+        pronunciations: 1,
+        middle: true,
+        old: true,
+    },
+    Canary {
+        c: '水',
+        note: "most modern varieties",
+        redirect: false,
+        pronunciations: 1,
+        middle: true,
+        old: true,
+    },
+    Canary {
+        c: '发',
+        note: "simplified form",
+        redirect: true,
+        pronunciations: 0,
+        middle: false,
+        old: false,
+    },
+];
+
+struct Report {
+    problems: usize,
+    /// Only print what did not match.
+    quiet: bool,
+}
+
+impl Report {
+    fn ok(&mut self, what: &str) {
+        if self.quiet {
+            return;
+        }
+        println!("\t\x1b[32;1mok\x1b[0m\t\t{}", what);
+    }
+
+    fn empty(&mut self, what: &str) {
+        self.problems += 1;
+        println!("\t\x1b[33;1mempty\x1b[0m\t\t{}", what);
+    }
+
+    fn malformed(&mut self, what: &str, e: &Error) {
+        self.problems += 1;
+        println!("\t\x1b[31;1mmalformed\x1b[0m\t{}: {}", what, e);
+    }
+
+    fn check(&mut self, what: &str, matched: bool) {
+        if matched {
+            self.ok(what);
+        } else {
+            self.empty(what);
+        }
+    }
+}
+
+/// Fetches every canary and runs each extractor on it, reporting what matched, what came back
+/// empty and what could not be parsed. Fails if anything did not match.
+pub fn run(args: &Args) -> Result<()> {
+    let cache = Cache::new(args);
+    let mut report = Report {
+        problems: 0,
+        quiet: args.quiet,
+    };
+    let mut varieties = HashSet::new();
+
+    for (i, canary) in CANARIES.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        println!("\x1b[31;1m{} ({})\x1b[0m", canary.c, canary.note);
+
+        // NOTE: A cached page could hide a change to Wiktionary, so canaries are always fetched
+        // unless there is no network.
+        let page = if args.offline {
+            cache.get(canary.c)
+        } else {
+            cache.fetch(canary.c)
+        };
+        let page = match page {
+            Ok(Some(page)) => page,
+            Ok(None) => {
+                report.empty("page");
+                continue;
+            }
+            Err(e) => {
+                report.malformed("page", &e);
+                continue;
+            }
+        };
+
+        match isolate_section(&page, "Translingual") {
+            Ok(section) => report.check(
+                "Translingual: Han char",
+                !translingual::fetch(section).is_empty(),
+            ),
+            Err(e) => report.malformed("Translingual", &e),
+        }
+
+        // NOTE: Not every canary has Japanese, Korean or Vietnamese entries, so only a section
+        // without readings counts.
+        if let Ok(section) = isolate_section(&page, "Japanese") {
+            report.check(
+                "Japanese: ja-readings",
+                !japanese::fetch(section).is_empty(),
+            );
+        }
+
+        if let Ok(section) = isolate_section(&page, "Korean") {
+            report.check("Korean: ko-hanja", !korean::fetch(section).is_empty());
+        }
+        if let Ok(section) = isolate_section(&page, "Vietnamese") {
+            report.check(
+                "Vietnamese: vi-readings",
+                !vietnamese::fetch(section).is_empty(),
+            );
+        }
+
+        let chinese = match isolate_chinese_section(&page) {
+            Ok(chinese) => {
+                report.ok("isolate_chinese_section");
+                chinese
+            }
+            Err(e) => {
+                report.malformed("isolate_chinese_section", &e);
+                continue;
+            }
+        };
+
+        if canary.redirect {
+            report.check(
+                "see_redirect",
+                !Page::Html(page.clone()).redirects().is_empty(),
+            );
+        }
+
+        let tree = Tree::html(chinese);
+        let pronunciations = tree.pronunciations();
+        if canary.pronunciations > 0 {
+            report.check(
+                &format!(
+                    "html_heading: Pronunciation ({} of at least {})",
+                    pronunciations.len(),
+                    canary.pronunciations
+                ),
+                pronunciations.len() >= canary.pronunciations,
+            );
+        }
+        report.check(
+            "Glyph origin / Etymology: text",
+            tree.origins()
+                .iter()
+                .any(|s| !etymology::from_text(&s.title, &html_text(tree.intro(s))).is_empty()),
+        );
+        for etymology in tree.etymologies() {
+            report.check(
+                &format!("{}: Pronunciation", etymology.title),
+                !tree.pronunciations_of(etymology).is_empty(),
+            );
+        }
+
+        for (j, pronunciation) in pronunciations.into_iter().enumerate() {
+            let prefix = format!("Pronunciation {}", j + 1);
+            let definitions = tree.definitions(pronunciation);
+            report.check(&format!("{}: Definitions", prefix), !definitions.is_empty());
+            report.check(
+                &format!("{}: senses", prefix),
+                definitions.iter().all(|pos| {
+                    !definitions::fetch(&pos.title, tree.body(pos))
+                        .senses
+                        .is_empty()
+                }),
+            );
+            let section = tree.body(pronunciation);
+            report.check(
+                &format!("{}: modern_data", prefix),
+                regexes().modern_data.is_match(section),
+            );
+            match modern::fetch(section) {
+                Ok(data) => varieties.extend(
+                    data.fields()
+                        .into_iter()
+                        .filter(|(_, value)| value.is_some())
+                        .map(|(name, _)| name),
+                ),
+                Err(e) => report.malformed(&format!("{}: Modern", prefix), &e),
+            }
+
+            // NOTE: Only the first pronunciation is required to have the tables.
+            if j > 0 {
+                continue;
+            }
+            if canary.middle {
+                check_middle(&mut report, &prefix, section);
+            }
+            if canary.old {
+                check_old(&mut report, &prefix, section);
+            }
+        }
+    }
+
+    println!();
+    println!("\x1b[31;1mModern varieties\x1b[0m");
+    for (name, _) in modern::Data::default().fields() {
+        report.check(name, varieties.contains(name));
+    }
+
+    match report.problems {
+        0 => Ok(()),
+        problems => Err(Error::Drift(problems)),
+    }
+}
+
+fn check_middle(report: &mut Report, prefix: &str, section: &str) {
+    let what = format!("{}: Middle Chinese", prefix);
+    let datas = match middle::fetch(section) {
+        Ok(datas) if datas.is_empty() => return report.empty(&what),
+        Ok(datas) => datas,
+        Err(e) => return report.malformed(&what, &e),
+    };
+    report.ok(&what);
+
+    let fields = [
+        ("Initial", datas.iter().all(|d| !d.init.is_empty())),
+        ("Final", datas.iter().all(|d| !d.fin.is_empty())),
+        ("Fanqie", datas.iter().all(|d| d.fanqie != "切")),
+        ("Baxter", datas.iter().all(|d| !d.baxter.is_empty())),
+        (
+            "Expected Mandarin Reflex",
+            datas.iter().all(|d| !d.expected_mandarin.is_empty()),
+        ),
+        (
+            "Expected Cantonese Reflex",
+            datas.iter().all(|d| !d.expected_cantonese.is_empty()),
+        ),
+    ];
+    for (field, matched) in fields {
+        report.check(&format!("{}: {}", what, field), matched);
+    }
+}
+
+fn check_old(report: &mut Report, prefix: &str, section: &str) {
+    let what = format!("{}: Old Chinese (Baxter–Sagart)", prefix);
+    match old_bs::fetch(section) {
+        Ok(datas) => report.check(
+            &what,
+            !datas.is_empty() && datas.iter().all(|d| !d.old_chinese.is_empty()),
+        ),
+        Err(e) => report.malformed(&what, &e),
+    }
+
+    let what = format!("{}: Old Chinese (Zhengzhang)", prefix);
+    match old_zh::fetch(section) {
+        Ok(datas) => report.check(
+            &what,
+            !datas.is_empty() && datas.iter().all(|d| !d.old_chinese.is_empty()),
+        ),
+        Err(e) => report.malformed(&what, &e),
+    }
+}
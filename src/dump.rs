@@ -0,0 +1,140 @@
+use flate2::read::MultiGzDecoder;
+use serde_json::Value;
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::Path,
+};
+use tar::Archive;
+
+use crate::{request::write_fixture, utils::has_chinese_section, Args};
+
+// NOTE: The HTML is escaped inside the JSON, so this is what a Chinese heading looks like in a raw
+// line. Checking it first saves parsing millions of articles we do not care about.
+const CHINESE_HEADING: &str = r#"id=\"Chinese\""#;
+
+/// Imports single-character pages with a Chinese section from a Wikimedia Enterprise HTML dump
+/// (a tar.gz of NDJSON articles) into `out`, which can then be used with `--source dump:<out>`.
+pub fn import(args: &Args, dump: &Path, out: &Path) -> io::Result<()> {
+    let mut archive = Archive::new(MultiGzDecoder::new(File::open(dump)?));
+    let mut imported = 0;
+
+    for file in archive.entries()? {
+        let file = file?;
+        if file.path()?.extension().is_none_or(|ext| ext != "ndjson") {
+            continue;
+        }
+
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            if !line.contains(CHINESE_HEADING) {
+                continue;
+            }
+            let Ok(article) = serde_json::from_str::<Value>(&line) else {
+                continue;
+            };
+            let Some((c, html, revision)) = parse_article(&article) else {
+                continue;
+            };
+            if !has_chinese_section(html) {
+                continue;
+            }
+
+            write_fixture(out, c, "html", html.as_bytes(), revision)?;
+            imported += 1;
+            if !args.quiet && imported % 1000 == 0 {
+                eprintln!("Imported {} pages...", imported);
+            }
+        }
+    }
+
+    if !args.quiet {
+        eprintln!("Imported {} pages into {}.", imported, out.display());
+    }
+
+    Ok(())
+}
+
+fn parse_article(article: &Value) -> Option<(char, &str, u64)> {
+    let mut name = article.get("name")?.as_str()?.chars();
+    let c = match (name.next(), name.next()) {
+        (Some(c), None) => c,
+        _ => return None,
+    };
+    let html = article.get("article_body")?.get("html")?.as_str()?;
+    let revision = article
+        .get("version")
+        .and_then(|v| v.get("identifier"))
+        .and_then(Value::as_u64)
+        .unwrap_or(0);
+    Some((c, html, revision))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use flate2::{write::GzEncoder, Compression};
+    use serde_json::json;
+    use std::{env, fs};
+    use tar::{Builder, Header};
+
+    fn article(name: &str, language: &str, revision: u64) -> String {
+        json!({
+            "name": name,
+            "version": { "identifier": revision },
+            "article_body": {
+                "html": format!(r#"<h2 id="{0}">{0}</h2><p>{1}</p>"#, language, name),
+            },
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn imports_chinese_articles() {
+        let dir = env::temp_dir().join(format!("wangyun-dump-{}", std::process::id()));
+        let out = dir.join("out");
+        let dump = dir.join("dump.tar.gz");
+        fs::create_dir_all(&dir).expect("failed to create");
+
+        let ndjson = [
+            article("字", "Chinese", 123),
+            article("a", "English", 1),
+            article("字母", "Chinese", 2),
+        ]
+        .join("\n");
+        let mut builder = Builder::new(GzEncoder::new(
+            File::create(&dump).expect("failed to create"),
+            Compression::default(),
+        ));
+        let mut header = Header::new_gnu();
+        header.set_size(ndjson.len() as u64);
+        header.set_mode(0o644);
+        builder
+            .append_data(&mut header, "enwiktionary_0.ndjson", ndjson.as_bytes())
+            .expect("failed to append");
+        builder
+            .into_inner()
+            .and_then(|gz| gz.finish())
+            .expect("failed to finish");
+
+        let args = Args::parse_from(["wangyun", "字", "--quiet"]);
+        let imported = import(&args, &dump, &out);
+        let mut files = fs::read_dir(&out)
+            .map(|dir| {
+                dir.flatten()
+                    .map(|f| f.file_name().to_string_lossy().into_owned())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        files.sort();
+        let page = crate::request::read_fixture(&out, '字', "html");
+        fs::remove_dir_all(&dir).expect("failed to clean up");
+
+        imported.expect("failed to import");
+        assert_eq!(files, ["5b57.html", "5b57.json"]);
+        let page = page.expect("not imported");
+        assert_eq!(page.revision, 123);
+        assert!(page.body.contains("<p>字</p>"));
+    }
+}
@@ -0,0 +1,163 @@
+use std::{fmt::Display, io, path::Path, process::ExitCode};
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    /// A request failed even after retrying. Exits with 3.
+    Network(reqwest::Error),
+    /// The server still reported replication lag (`maxlag`) after every retry. Exits with 3.
+    Lagged,
+    /// A local file (cache, dump, archive) could not be read or written. Exits with 4.
+    Io(io::Error),
+    /// The page has no section with this name. Exits with 5.
+    MissingSection(&'static str),
+    /// A row of a table is missing or has a value we do not understand. Exits with 6.
+    MalformedTable {
+        table: &'static str,
+        field: &'static str,
+        value: String,
+    },
+    /// The `data-mw` of a template is not valid JSON. Exits with 7.
+    BadJson(serde_json::Error),
+    /// `doctor` found this many checks that did not match. Exits with 8.
+    Drift(usize),
+    /// The source only has some fields of a reading, e.g. a dump that only keeps Baxter's
+    /// transcription. Exits with 9.
+    Partial {
+        what: String,
+        missing: Vec<&'static str>,
+    },
+    /// Some lookups failed but the rest were printed. Exits with the code of the first failure.
+    Incomplete { failed: usize, code: u8 },
+}
+
+impl Error {
+    pub fn malformed(table: &'static str, field: &'static str, value: impl Into<String>) -> Self {
+        Self::MalformedTable {
+            table,
+            field,
+            value: value.into(),
+        }
+    }
+
+    /// An I/O error with the file it happened on.
+    pub fn io_at(path: &Path, e: io::Error) -> Self {
+        Self::Io(io::Error::new(
+            e.kind(),
+            format!("{}: {}", path.display(), e),
+        ))
+    }
+
+    pub fn code(&self) -> u8 {
+        match self {
+            Self::Network(_) | Self::Lagged => 3,
+            Self::Io(_) => 4,
+            Self::MissingSection(_) => 5,
+            Self::MalformedTable { .. } => 6,
+            Self::BadJson(_) => 7,
+            Self::Drift(_) => 8,
+            Self::Partial { .. } => 9,
+            Self::Incomplete { code, .. } => *code,
+        }
+    }
+
+    pub fn exit_code(&self) -> ExitCode {
+        ExitCode::from(self.code())
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Network(e) => write!(f, "network error: {}", e),
+            Self::Lagged => write!(f, "network error: server still lagged after retrying"),
+            Self::Io(e) => write!(f, "I/O error: {}", e),
+            Self::MissingSection(name) => write!(f, "no {} section found", name),
+            Self::MalformedTable {
+                table,
+                field,
+                value,
+            } => {
+                if value.is_empty() {
+                    write!(f, "malformed {} table: missing {}", table, field)
+                } else {
+                    write!(
+                        f,
+                        "malformed {} table: invalid {} `{}`",
+                        table, field, value
+                    )
+                }
+            }
+            Self::BadJson(e) => write!(f, "invalid JSON data: {}", e),
+            Self::Drift(problems) => write!(f, "{} checks did not match", problems),
+            Self::Partial { what, missing } => {
+                write!(f, "the source has no {} for {}", missing.join(", "), what)
+            }
+            Self::Incomplete { failed, .. } => write!(f, "{} lookup(s) failed", failed),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Self::Network(e)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Self::BadJson(e)
+    }
+}
+
+/// Errors of single characters or fields that did not stop the run.
+pub struct Diagnostics {
+    strict: bool,
+    errors: Vec<(String, Error)>,
+}
+
+impl Diagnostics {
+    pub fn new(strict: bool) -> Self {
+        Self {
+            strict,
+            errors: vec![],
+        }
+    }
+
+    /// Returns the value if `result` is ok. Otherwise the error is returned in strict mode, or
+    /// recorded under `context` (e.g. the character) and `None` is returned.
+    pub fn check<T>(&mut self, context: impl Display, result: Result<T>) -> Result<Option<T>> {
+        match result {
+            Ok(value) => Ok(Some(value)),
+            Err(e) if self.strict => Err(e),
+            Err(e) => {
+                self.errors.push((context.to_string(), e));
+                Ok(None)
+            }
+        }
+    }
+
+    /// Prints the recorded errors and turns them into [`Error::Incomplete`].
+    pub fn finish(self) -> Result<()> {
+        for (context, e) in self.errors.iter() {
+            eprintln!("{}: {}", context, e);
+        }
+
+        match self.errors.first() {
+            Some((_, e)) => Err(Error::Incomplete {
+                failed: self.errors.len(),
+                code: e.code(),
+            }),
+            None => Ok(()),
+        }
+    }
+}
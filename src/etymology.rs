@@ -0,0 +1,138 @@
+use regex::Regex;
+
+use crate::{normalize::normalize, regexes::regexes};
+
+/// A glyph origin or etymology section.
+#[derive(Debug, Clone, Default)]
+pub struct Data {
+    /// `Glyph origin`, `Etymology` or `Etymology N`.
+    pub title: String,
+    pub text: String,
+    /// Semantic components of a phono-semantic compound.
+    pub semantic: Vec<String>,
+    /// Phonetic components of a phono-semantic compound.
+    pub phonetic: Vec<String>,
+    /// Proto-Sino-Tibetan reconstructions, e.g. `*s-ŋa`.
+    pub sino_tibetan: Vec<String>,
+    /// Old Chinese reconstructions, e.g. `*ŋa`.
+    pub old_chinese: Vec<String>,
+}
+
+impl Data {
+    /// Whether the section says nothing, e.g. an `Etymology 2` that only has subsections.
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+            && self.semantic.is_empty()
+            && self.phonetic.is_empty()
+            && self.sino_tibetan.is_empty()
+            && self.old_chinese.is_empty()
+    }
+}
+
+/// Reads the components and reconstructions mentioned in the plain text of a section.
+pub fn from_text(title: &str, text: &str) -> Data {
+    let text = normalize(text);
+    let mut data = Data {
+        title: title.to_string(),
+        ..Data::default()
+    };
+    let re = regexes();
+    push_matches(&mut data.semantic, &re.etymology_semantic, &text);
+    push_matches(&mut data.phonetic, &re.etymology_phonetic, &text);
+    push_matches(&mut data.sino_tibetan, &re.etymology_sino_tibetan, &text);
+    push_matches(&mut data.old_chinese, &re.etymology_old_chinese, &text);
+    data.text = text;
+    data
+}
+
+/// Adds what an etymology template says to `data`. `get` returns the parameter `name`, positional
+/// parameters being named by their index starting from `1`.
+pub fn add_template(data: &mut Data, name: &str, get: impl Fn(&str) -> Option<String>) {
+    match name {
+        // NOTE: `c1=s`, `c2=p` give the role of each component. `sp` is both.
+        "Han compound" => {
+            for i in 1.. {
+                let Some(component) = get(&i.to_string()) else {
+                    break;
+                };
+                let role = get(&format!("c{}", i)).unwrap_or_default();
+                if role.contains('s') {
+                    push(&mut data.semantic, component.clone());
+                }
+                if role.contains('p') {
+                    push(&mut data.phonetic, component);
+                }
+            }
+        }
+        // `{{inh|zh|sit-pro|*s-ŋa}}`, `{{cog|sit-pro|*s-ŋa}}`
+        "inh" | "inh+" | "der" | "der+" | "bor" | "bor+" | "cog" => {
+            let offset = if name == "cog" { 0 } else { 1 };
+            let lang = get(&(offset + 1).to_string());
+            if lang.as_deref() == Some("sit-pro") {
+                if let Some(form) = get(&(offset + 2).to_string()) {
+                    push(&mut data.sino_tibetan, form);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Adds the first group of every match of `re`, which may be a list like `*ŋa, *ŋas`.
+fn push_matches(values: &mut Vec<String>, re: &Regex, text: &str) {
+    for caps in re.captures_iter(text) {
+        for value in caps[1].split(", ") {
+            push(values, value.trim_end_matches('.').to_string());
+        }
+    }
+}
+
+fn push(values: &mut Vec<String>, value: String) {
+    if !values.contains(&value) {
+        values.push(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::normalize::html_text;
+
+    #[test]
+    fn reads_components_and_reconstructions() {
+        let html = concat!(
+            "<p>Phono-semantic compound (<i>形聲</i>, OC *zlɯs): ",
+            "semantic <a href=\"./宀\">宀</a> + phonetic <a href=\"./子\">子</a> (OC *ʔslɯʔ).</p>",
+            "<p>From Proto-Sino-Tibetan *s-ŋa, *ŋa.</p>",
+        );
+        let data = from_text("Glyph origin", &html_text(html));
+        assert_eq!(data.semantic, ["宀"]);
+        assert_eq!(data.phonetic, ["子"]);
+        assert_eq!(data.sino_tibetan, ["*s-ŋa", "*ŋa"]);
+        assert_eq!(data.old_chinese, ["*zlɯs", "*ʔslɯʔ"]);
+        assert!(data
+            .text
+            .starts_with("Phono-semantic compound (形聲, OC *zlɯs)"));
+    }
+
+    #[test]
+    fn reads_templates() {
+        let mut data = Data::default();
+        let compound = [("1", "氵"), ("2", "工"), ("c1", "s"), ("c2", "p")];
+        add_template(&mut data, "Han compound", |name| {
+            compound
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, v)| v.to_string())
+        });
+        let inh = [("1", "zh"), ("2", "sit-pro"), ("3", "*kroŋ")];
+        add_template(&mut data, "inh", |name| {
+            inh.iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, v)| v.to_string())
+        });
+        assert_eq!(data.semantic, ["氵"]);
+        assert_eq!(data.phonetic, ["工"]);
+        assert_eq!(data.sino_tibetan, ["*kroŋ"]);
+    }
+}
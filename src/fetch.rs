@@ -0,0 +1,470 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+};
+
+use crate::{
+    cache::{Cache, Kind},
+    error::{Diagnostics, Error, Result},
+    kaikki,
+    normalize::canonical_chars,
+    request::read_fixture,
+    source::{Page, Redirect, Relation, Source},
+    utils::is_han,
+    wikitext::{self, strip_comments, Module, Modules},
+    zim::Zim,
+    Args,
+};
+
+pub const DEFAULT_JOBS: usize = 4;
+/// Redirects are followed at most this many times.
+const MAX_REDIRECTS: usize = 3;
+
+type Results = HashMap<char, Result<Option<Page>>>;
+/// Each character that redirected and how it is related to the next one.
+pub type Chain = Vec<(char, Relation)>;
+
+/// What the lookup of one input character found.
+pub enum Lookup {
+    /// One page per traditional form, as a simplified character may stand for several.
+    Found(Vec<Target>),
+    /// The page only has entries in other languages.
+    NoChinese,
+    /// The source has no page for the character.
+    Missing,
+    /// The page could not be fetched or read. The error is in the diagnostics.
+    Failed,
+    /// Spaces, punctuation and other characters that are never looked up.
+    NotHan,
+}
+
+/// The lookup of one character of the input.
+pub struct Entry {
+    /// The character as it was typed, with any variation selector.
+    pub input: String,
+    /// The unified ideograph `input` was looked up as.
+    pub c: char,
+    pub lookup: Lookup,
+}
+
+pub struct Target {
+    pub c: char,
+    /// The sense of the simplified character this form covers.
+    pub sense: Option<String>,
+    /// How the input character is related to `c`: the redirects followed to get there, starting
+    /// from the input character. Empty if they are the same.
+    pub chain: Chain,
+    pub page: Page,
+}
+
+/// Fetches the page of every character in the input, following simplified → traditional links.
+/// Each distinct page is only fetched once. The returned lookups follow the input order, with
+/// characters that are not Han passed through.
+///
+/// Characters that failed are recorded in `diags`. Only a source that cannot be opened at all
+/// fails the whole lookup.
+pub fn fetch_pages(args: &Args, diags: &mut Diagnostics) -> Result<Vec<Entry>> {
+    let cache = match args.source {
+        Source::Wikitext => Cache::of(args, Kind::Wikitext),
+        _ => Cache::new(args),
+    };
+    // NOTE: Data modules are only fetched when their readings are shown.
+    let module_caches = if args.middle || args.old || args.baxter {
+        Module::ALL
+            .iter()
+            .map(|module| (*module, cache.sibling(args, Kind::Module(*module))))
+            .collect()
+    } else {
+        vec![]
+    };
+    let fetch_all = |chars: &[char]| match args.source {
+        Source::Wiktionary => Ok(fetch_all(chars, args.jobs, |c| {
+            cache.get(c).map(|page| page.map(Page::Html))
+        })),
+        Source::Wikitext => Ok(fetch_all(chars, args.jobs, |c| {
+            wikitext_page(&cache, &module_caches, c)
+        })),
+        Source::Kaikki(ref path) => lookup_kaikki(path, chars),
+        Source::Dump(ref dir) => Ok(lookup_dump(dir, chars)),
+        Source::Zim(ref path) => lookup_zim(path, chars),
+    };
+    let input = canonical_chars(args.input());
+    let canonicalized = input
+        .iter()
+        .filter(|(original, c)| *original != c.to_string())
+        .map(|(original, c)| format!("{} → {}", original, c))
+        .collect::<Vec<_>>();
+    if !canonicalized.is_empty() && !args.quiet {
+        eprintln!("Canonicalized: {}", canonicalized.join(", "));
+    }
+
+    let mut seen = HashSet::new();
+    let uniq = input
+        .iter()
+        .map(|(_, c)| *c)
+        .filter(|c| is_han(*c) && seen.insert(*c))
+        .collect::<Vec<_>>();
+    let mut results = fetch_all(&uniq)?;
+
+    // NOTE: Pages are fetched one redirect level at a time so each level is still fetched in
+    // parallel.
+    let mut redirects = HashMap::new();
+    let mut own_entries = HashSet::new();
+    let mut level = uniq;
+    for depth in 0..=MAX_REDIRECTS {
+        let mut next = vec![];
+        for c in level.iter() {
+            if let Some(Ok(Some(page))) = results.get(c) {
+                let page_redirects = page.redirects();
+                next.extend(
+                    page_redirects
+                        .iter()
+                        .map(|r| r.c)
+                        .filter(|c| seen.insert(*c)),
+                );
+                if !page_redirects.is_empty() {
+                    // NOTE: Pages like 里 or 后 only send some senses to another form and keep
+                    // an entry of their own for the rest.
+                    if page.pronunciations().is_ok_and(|p| !p.is_empty()) {
+                        own_entries.insert(*c);
+                    }
+                    redirects.insert(*c, page_redirects);
+                }
+            }
+        }
+        if next.is_empty() || depth == MAX_REDIRECTS {
+            break;
+        }
+        // NOTE: A dump is read once, with the pages its entries redirect to.
+        let unfetched = next
+            .iter()
+            .copied()
+            .filter(|c| !results.contains_key(c))
+            .collect::<Vec<_>>();
+        if !unfetched.is_empty() {
+            results.extend(fetch_all(&unfetched)?);
+        }
+        level = next;
+    }
+
+    let mut pages = vec![];
+    let mut missing = vec![];
+    let mut no_chinese = vec![];
+    let mut unresolved = vec![];
+    let mut failed = vec![];
+    let mut failed_chars = HashSet::new();
+    for (original, c) in input {
+        if !is_han(c) {
+            pages.push(Entry {
+                input: original,
+                c,
+                lookup: Lookup::NotHan,
+            });
+            continue;
+        }
+        let mut targets = vec![];
+        resolve(
+            &redirects,
+            &own_entries,
+            c,
+            None,
+            vec![],
+            &mut targets,
+            &mut unresolved,
+        );
+
+        let mut found = vec![];
+        let mut only_other_languages = false;
+        let mut any_failed = false;
+        for (c, sense, chain) in targets {
+            match results.get(&c) {
+                Some(Ok(Some(page))) if !page.has_chinese() => {
+                    only_other_languages = true;
+                    no_chinese.push(c);
+                }
+                Some(Ok(Some(page))) => found.push(Target {
+                    c,
+                    sense,
+                    chain,
+                    page: page.clone(),
+                }),
+                Some(Ok(None)) => missing.push(c),
+                // NOTE: Errors are taken out of the map so they are only reported once.
+                Some(Err(_)) => {
+                    if let Some(Err(e)) = results.remove(&c) {
+                        failed.push((c, e));
+                        failed_chars.insert(c);
+                    }
+                    any_failed = true;
+                }
+                None => any_failed |= failed_chars.contains(&c),
+            }
+        }
+
+        // NOTE: Every Han character keeps an entry so that the output lines up with the input.
+        let lookup = if !found.is_empty() {
+            Lookup::Found(found)
+        } else if only_other_languages {
+            Lookup::NoChinese
+        } else if any_failed {
+            Lookup::Failed
+        } else {
+            Lookup::Missing
+        };
+        pages.push(Entry {
+            input: original,
+            c,
+            lookup,
+        });
+    }
+
+    if !missing.is_empty() {
+        eprintln!("Missing: {}", missing.into_iter().collect::<String>());
+    }
+    if !unresolved.is_empty() && !args.quiet {
+        unresolved.dedup();
+        eprintln!(
+            "Stopped following redirects at: {}",
+            unresolved.into_iter().collect::<String>()
+        );
+    }
+    if !no_chinese.is_empty() {
+        eprintln!(
+            "No Chinese entry: {}",
+            no_chinese.into_iter().collect::<String>()
+        );
+    }
+    let retried = cache.client().retried();
+    if !retried.is_empty() && !args.quiet {
+        eprintln!("Retried: {}", retried.into_iter().collect::<String>());
+    }
+    for (c, e) in failed {
+        diags.check(c, Err::<(), _>(e))?;
+    }
+
+    Ok(pages)
+}
+
+/// Follows the redirects of `c` depth-first and adds every character it ends up at to
+/// `targets`. A character in `own_entries` is a target too, before the ones it points to. A
+/// redirect back into the chain or past [`MAX_REDIRECTS`] ends at the current character and is
+/// added to `unresolved`.
+fn resolve(
+    redirects: &HashMap<char, Vec<Redirect>>,
+    own_entries: &HashSet<char>,
+    c: char,
+    sense: Option<String>,
+    chain: Chain,
+    targets: &mut Vec<(char, Option<String>, Chain)>,
+    unresolved: &mut Vec<char>,
+) {
+    let Some(next) = redirects.get(&c) else {
+        if !targets.iter().any(|(t, _, _)| *t == c) {
+            targets.push((c, sense, chain));
+        }
+        return;
+    };
+
+    if own_entries.contains(&c) && !targets.iter().any(|(t, _, _)| *t == c) {
+        targets.push((c, sense.clone(), chain.clone()));
+    }
+
+    let mut followed = false;
+    for redirect in next {
+        if redirect.c == c || chain.iter().any(|(from, _)| *from == redirect.c) {
+            continue;
+        }
+        if chain.len() >= MAX_REDIRECTS {
+            break;
+        }
+        let mut chain = chain.clone();
+        chain.push((c, redirect.relation));
+        let sense = sense.clone().or_else(|| redirect.sense.clone());
+        resolve(
+            redirects,
+            own_entries,
+            redirect.c,
+            sense,
+            chain,
+            targets,
+            unresolved,
+        );
+        followed = true;
+    }
+
+    if !followed {
+        unresolved.push(c);
+        if !targets.iter().any(|(t, _, _)| *t == c) {
+            targets.push((c, sense, chain));
+        }
+    }
+}
+
+/// Fetches `chars` with up to `jobs` requests in flight at once.
+fn fetch_all(
+    chars: &[char],
+    jobs: usize,
+    get: impl Fn(char) -> Result<Option<Page>> + Sync,
+) -> Results {
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Results::new());
+
+    thread::scope(|s| {
+        for _ in 0..jobs.clamp(1, chars.len().max(1)) {
+            s.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(&c) = chars.get(i) else {
+                    break;
+                };
+                let page = get(c);
+                results.lock().expect("poisoned lock").insert(c, page);
+            });
+        }
+    });
+
+    results.into_inner().expect("poisoned lock")
+}
+
+/// The wikitext page of `c`, with the data modules its `{{zh-pron}}` asks for out of
+/// `module_caches`.
+fn wikitext_page(
+    cache: &Cache,
+    module_caches: &[(Module, Cache)],
+    c: char,
+) -> Result<Option<Page>> {
+    let Some(text) = cache.get(c)? else {
+        return Ok(None);
+    };
+    let text = strip_comments(&text).into_owned();
+    let mut modules = Modules::default();
+    let wanted = wikitext::chinese_section(&text)
+        .map(wikitext::modules)
+        .unwrap_or_default();
+    for (module, module_cache) in module_caches {
+        if wanted.contains(module) {
+            *modules.get_mut(*module) = module_cache.get(c)?;
+        }
+    }
+    Ok(Some(Page::Wikitext(text, modules)))
+}
+
+fn lookup_kaikki(path: &Path, chars: &[char]) -> Result<Results> {
+    let entries = kaikki::lookup(path, chars, MAX_REDIRECTS).map_err(|e| Error::io_at(path, e))?;
+    Ok(entries
+        .into_iter()
+        .map(|(c, entry)| (c, Ok(entry.map(|entry| Page::Records(Box::new(entry))))))
+        .collect())
+}
+
+fn lookup_dump(dir: &Path, chars: &[char]) -> Results {
+    chars
+        .iter()
+        .map(|c| {
+            (
+                *c,
+                Ok(read_fixture(dir, *c, "html").map(|r| Page::Html(r.body))),
+            )
+        })
+        .collect()
+}
+
+fn lookup_zim(path: &Path, chars: &[char]) -> Result<Results> {
+    let mut zim = Zim::open(path).map_err(|e| Error::io_at(path, e))?;
+    Ok(chars
+        .iter()
+        .map(|c| {
+            let page = zim.page(*c).map_err(|e| Error::io_at(path, e));
+            (*c, page.map(|page| page.map(Page::Html)))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redirect(c: char, sense: &str, relation: Relation) -> Redirect {
+        Redirect {
+            c,
+            sense: Some(sense.to_string()),
+            relation,
+        }
+    }
+
+    fn targets(
+        redirects: &HashMap<char, Vec<Redirect>>,
+        own_entries: &HashSet<char>,
+        c: char,
+    ) -> Vec<(char, Option<String>)> {
+        let mut targets = vec![];
+        resolve(
+            redirects,
+            own_entries,
+            c,
+            None,
+            vec![],
+            &mut targets,
+            &mut vec![],
+        );
+        targets
+            .into_iter()
+            .map(|(c, sense, _)| (c, sense))
+            .collect()
+    }
+
+    #[test]
+    fn replaces_a_redirect_page() {
+        let redirects = HashMap::from([(
+            '发',
+            vec![
+                redirect('發', "to send out", Relation::Simplified),
+                redirect('髮', "hair", Relation::Simplified),
+            ],
+        )]);
+        assert_eq!(
+            targets(&redirects, &HashSet::new(), '发'),
+            [
+                ('發', Some("to send out".to_string())),
+                ('髮', Some("hair".to_string()))
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_an_own_entry() {
+        let redirects =
+            HashMap::from([('里', vec![redirect('裏', "inside", Relation::Simplified)])]);
+        assert_eq!(
+            targets(&redirects, &HashSet::from(['里']), '里'),
+            [('里', None), ('裏', Some("inside".to_string()))]
+        );
+    }
+
+    #[test]
+    fn stops_at_cycles() {
+        let redirects = HashMap::from([
+            ('裡', vec![redirect('裏', "inside", Relation::Variant)]),
+            ('裏', vec![redirect('裡', "inside", Relation::Variant)]),
+        ]);
+        let mut targets = vec![];
+        let mut unresolved = vec![];
+        resolve(
+            &redirects,
+            &HashSet::new(),
+            '裡',
+            None,
+            vec![],
+            &mut targets,
+            &mut unresolved,
+        );
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0].0, '裏');
+        assert_eq!(targets[0].2, [('裡', Relation::Variant)]);
+        assert_eq!(unresolved, ['裏']);
+    }
+}
@@ -0,0 +1,123 @@
+use crate::{
+    utils::{param, template_params},
+    wikitext,
+};
+
+/// A reading in modern kana, with the historical kana it was spelled with if they differ.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Reading {
+    pub kana: String,
+    pub historical: Option<String>,
+}
+
+/// The readings of `{{ja-readings}}` in the Japanese section.
+#[derive(Debug, Clone, Default)]
+pub struct Data {
+    pub go_on: Vec<Reading>,
+    pub kan_on: Vec<Reading>,
+    pub to_on: Vec<Reading>,
+    pub kanyo_on: Vec<Reading>,
+    /// On'yomi the page does not classify.
+    pub on: Vec<Reading>,
+    pub kun: Vec<Reading>,
+}
+
+impl Data {
+    /// Every kind of reading with its name.
+    pub fn fields(&self) -> [(&'static str, &Vec<Reading>); 6] {
+        [
+            ("Go-on", &self.go_on),
+            ("Kan-on", &self.kan_on),
+            ("Tō-on", &self.to_on),
+            ("Kan'yō-on", &self.kanyo_on),
+            ("On'yomi", &self.on),
+            ("Kun'yomi", &self.kun),
+        ]
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields()
+            .iter()
+            .all(|(_, readings)| readings.is_empty())
+    }
+}
+
+/// Reads the parameters of `{{ja-readings}}` from the `data-mw` of a Parsoid HTML section.
+pub fn fetch(section: &str) -> Data {
+    match template_params(section, "ja-readings") {
+        Some(params) => from_params(|name| param(&params, name)),
+        None => Data::default(),
+    }
+}
+
+/// Reads `{{ja-readings}}` from the Japanese section of a wikitext page.
+pub fn from_wikitext(section: &str) -> Data {
+    match wikitext::templates(section, "ja-readings").first() {
+        Some(readings) => from_params(|name| readings.get(name).map(str::to_string)),
+        None => Data::default(),
+    }
+}
+
+/// Builds the data from the parameters of `{{ja-readings}}`.
+pub fn from_params(get: impl Fn(&str) -> Option<String>) -> Data {
+    let readings = |name| get(name).map(|s| readings(&s)).unwrap_or_default();
+    Data {
+        go_on: readings("goon"),
+        kan_on: readings("kanon"),
+        to_on: readings("toon"),
+        kanyo_on: readings("kanyoon"),
+        on: readings("on"),
+        kun: readings("kun"),
+    }
+}
+
+/// Splits `しょう<しやう, せい` into readings. The historical kana come after `<`.
+fn readings(list: &str) -> Vec<Reading> {
+    list.split([',', '、'])
+        .map(|reading| {
+            let reading = wikitext::plain(reading);
+            match reading.split_once('<') {
+                Some((kana, historical)) => Reading {
+                    kana: kana.trim().to_string(),
+                    historical: Some(historical.trim().to_string()),
+                },
+                None => Reading {
+                    kana: reading,
+                    historical: None,
+                },
+            }
+        })
+        .filter(|reading| !reading.kana.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{fixture, isolate_section};
+
+    fn reading(kana: &str, historical: Option<&str>) -> Reading {
+        Reading {
+            kana: kana.to_string(),
+            historical: historical.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn reads_readings_with_historical_kana() {
+        let page = fixture('行');
+        let data = fetch(isolate_section(&page, "Japanese").expect("no Japanese"));
+        assert_eq!(data.go_on, [reading("ぎょう", Some("ぎやう"))]);
+        assert_eq!(data.kan_on, [reading("こう", Some("かう"))]);
+        assert_eq!(data.to_on, [reading("あん", None)]);
+        assert!(data.kanyo_on.is_empty());
+        assert_eq!(
+            data.kun,
+            [
+                reading("いく", None),
+                reading("ゆく", None),
+                reading("おこなう", Some("おこなふ")),
+            ]
+        );
+    }
+}
@@ -0,0 +1,719 @@
+use flate2::read::MultiGzDecoder;
+use regex::Regex;
+use serde_json::Value;
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::{self, BufRead, BufReader, Read},
+    path::Path,
+};
+
+use crate::{
+    definitions::{Definitions, Sense},
+    etymology, japanese, korean, middle, modern, old_bs, old_zh,
+    source::{Redirect, Relation},
+    translingual,
+    utils::push_see_targets,
+    vietnamese,
+};
+
+/// A character entry built from every line of the dump with that headword.
+#[derive(Debug, Clone, Default)]
+pub struct Entry {
+    /// Set when the entry only points to other characters (e.g. a simplified form).
+    pub redirects: Vec<Redirect>,
+    pub pronunciations: Vec<Pronunciation>,
+    pub etymologies: Vec<etymology::Data>,
+    /// From the Translingual entry of the character.
+    pub translingual: translingual::Data,
+    /// From the entries in other languages, `None` if the dump has no such entry.
+    pub japanese: Option<japanese::Data>,
+    pub korean: Option<korean::Data>,
+    pub vietnamese: Option<vietnamese::Data>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Pronunciation {
+    /// `Etymology N` when the character has several, like the heading of an HTML page.
+    pub label: String,
+    pub definitions: Vec<Definitions>,
+    pub middle: Vec<middle::Data>,
+    pub old_bs: Vec<old_bs::Data>,
+    pub old_zh: Vec<old_zh::Data>,
+    pub modern: modern::Data,
+}
+
+/// Reads the entries of `chars` from a wiktextract JSONL dump, which may be gzip-compressed,
+/// along with the entries of the characters they redirect to, up to `depth` redirects away.
+/// Every character that was looked for is in the result, with `None` if the dump has no entry.
+pub fn lookup(
+    path: &Path,
+    chars: &[char],
+    depth: usize,
+) -> io::Result<HashMap<char, Option<Entry>>> {
+    // NOTE: Parsing every line of the dump is slow, so only lines that have one of the
+    // characters as a word get parsed.
+    let re = Regex::new(r#""word": ?"([^"\\])""#).expect("invalid regex");
+
+    // How many redirects away from the input each character is.
+    let mut wanted = chars.iter().map(|c| (*c, 0)).collect::<HashMap<_, _>>();
+    let mut pending = chars.to_vec();
+    let mut lines = HashMap::<char, Vec<Value>>::new();
+    let mut translingual = HashMap::<char, translingual::Data>::new();
+    let mut others = HashMap::<char, Vec<Value>>::new();
+
+    // NOTE: The characters a line redirects to are looked for in the same pass. Only those that
+    // were already passed need another one.
+    while !pending.is_empty() {
+        let mut looking = pending.drain(..).collect::<HashSet<_>>();
+        let mut passed = HashSet::new();
+        let mut words = vec![];
+        for line in open(path)?.lines() {
+            let line = line?;
+            // NOTE: The words of the line being read are only passed after it, as it may point
+            // to one of them.
+            passed.extend(words.drain(..));
+            words.extend(
+                re.captures_iter(&line)
+                    .filter_map(|caps| caps[1].chars().next()),
+            );
+            if !words.iter().any(|c| looking.contains(c)) {
+                continue;
+            }
+            let Some((c, json)) = parse(&line).filter(|(c, _)| looking.contains(c)) else {
+                continue;
+            };
+            match json.get("lang_code").and_then(Value::as_str) {
+                Some("zh") => {
+                    let distance = wanted[&c];
+                    let mut redirects = vec![];
+                    if distance < depth {
+                        push_redirects(&mut redirects, c, &json);
+                    }
+                    for redirect in redirects {
+                        if wanted.contains_key(&redirect.c) {
+                            continue;
+                        }
+                        wanted.insert(redirect.c, distance + 1);
+                        if passed.contains(&redirect.c) {
+                            pending.push(redirect.c);
+                        } else {
+                            looking.insert(redirect.c);
+                        }
+                    }
+                    lines.entry(c).or_default().push(json);
+                }
+                Some("mul") => {
+                    if let Some(data) = han_char(&json) {
+                        translingual.insert(c, data);
+                    }
+                }
+                Some("ja" | "ko" | "vi") => others.entry(c).or_default().push(json),
+                _ => {}
+            }
+        }
+    }
+
+    Ok(wanted
+        .into_keys()
+        .map(|c| {
+            let entry = lines.get(&c).map(|lines| {
+                let mut entry = entry(c, lines);
+                entry.translingual = translingual.remove(&c).unwrap_or_default();
+                let others = others.remove(&c).unwrap_or_default();
+                entry.japanese = language(&others, "ja").map(|lines| {
+                    template(&lines, "ja-readings")
+                        .map(|args| japanese::from_params(|name| arg(args, name)))
+                        .unwrap_or_default()
+                });
+                entry.korean = language(&others, "ko").map(|lines| {
+                    template(&lines, "ko-hanja")
+                        .map(|args| korean::from_params(|name| arg(args, name)))
+                        .unwrap_or_default()
+                });
+                entry.vietnamese = language(&others, "vi").map(|lines| {
+                    if let Some(args) = template(&lines, "vi-readings") {
+                        vietnamese::from_params(|name| arg(args, name))
+                    } else if let Some(args) = template(&lines, "vi-hantu") {
+                        vietnamese::from_hantu(|name| arg(args, name))
+                    } else {
+                        vietnamese::Data::default()
+                    }
+                });
+                entry
+            });
+            (c, entry)
+        })
+        .collect())
+}
+
+/// Parses a line whose word is a single character.
+fn parse(line: &str) -> Option<(char, Value)> {
+    let json = serde_json::from_str::<Value>(line).ok()?;
+    let mut chars = json.get("word")?.as_str()?.chars();
+    let (Some(c), None) = (chars.next(), chars.next()) else {
+        return None;
+    };
+    Some((c, json))
+}
+
+fn open(path: &Path) -> io::Result<Box<dyn BufRead>> {
+    let mut file = BufReader::new(File::open(path)?);
+    let is_gzip = file.fill_buf()?.starts_with(&[0x1f, 0x8b]);
+    let reader: Box<dyn Read> = if is_gzip {
+        Box::new(MultiGzDecoder::new(file))
+    } else {
+        Box::new(file)
+    };
+    Ok(Box::new(BufReader::new(reader)))
+}
+
+struct Sound<'a> {
+    pron: &'a str,
+    tags: Vec<String>,
+}
+
+impl Sound<'_> {
+    fn has(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+
+    fn has_any(&self, tags: &[&str]) -> bool {
+        tags.iter().any(|t| self.has(t))
+    }
+}
+
+fn entry(c: char, lines: &[Value]) -> Entry {
+    // Lines of the same etymology share their pronunciations.
+    let mut groups = Vec::<(u64, Vec<Sound>)>::new();
+    for line in lines {
+        let etymology = line
+            .get("etymology_number")
+            .and_then(Value::as_u64)
+            .unwrap_or(0);
+        let sounds = sounds(line);
+        match groups.iter_mut().find(|(e, _)| *e == etymology) {
+            Some((_, group)) => {
+                for sound in sounds {
+                    if !group
+                        .iter()
+                        .any(|s| s.pron == sound.pron && s.tags == sound.tags)
+                    {
+                        group.push(sound);
+                    }
+                }
+            }
+            None => groups.push((etymology, sounds)),
+        }
+    }
+    groups.retain(|(_, sounds)| !sounds.is_empty());
+
+    let mut redirects = vec![];
+    if groups.is_empty() {
+        for line in lines {
+            push_redirects(&mut redirects, c, line);
+        }
+    }
+
+    let numbered = groups.len() > 1;
+    Entry {
+        redirects,
+        etymologies: etymologies(lines),
+        pronunciations: groups
+            .iter()
+            .enumerate()
+            .map(|(i, (etymology, sounds))| Pronunciation {
+                label: match (numbered, etymology) {
+                    (false, _) => "Pronunciation".to_string(),
+                    (true, 0) => format!("Pronunciation {}", i + 1),
+                    (true, n) => format!("Etymology {}", n),
+                },
+                definitions: definitions(lines, *etymology),
+                ..pronunciation(sounds)
+            })
+            .collect(),
+        ..Entry::default()
+    }
+}
+
+/// The senses of the lines of `etymology`, one part of speech per line.
+fn definitions(lines: &[Value], etymology: u64) -> Vec<Definitions> {
+    let mut definitions = Vec::<Definitions>::new();
+    let lines = lines.iter().filter(|line| {
+        line.get("etymology_number")
+            .and_then(Value::as_u64)
+            .unwrap_or(0)
+            == etymology
+    });
+    for line in lines {
+        let pos = line
+            .get("pos_title")
+            .or_else(|| line.get("pos"))
+            .and_then(Value::as_str)
+            .map(capitalize)
+            .unwrap_or_else(|| "Definitions".to_string());
+        let mut senses = vec![];
+        for sense in line
+            .get("senses")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            // NOTE: A sub-sense lists the glosses of its parents before its own.
+            let path = sense
+                .get("glosses")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .collect::<Vec<_>>();
+            let labels = ["raw_tags", "tags"]
+                .into_iter()
+                .filter_map(|key| sense.get(key)?.as_array())
+                .flatten()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect();
+            insert_sense(&mut senses, &path, labels);
+        }
+        if !senses.is_empty() {
+            definitions.push(Definitions { pos, senses });
+        }
+    }
+    definitions
+}
+
+fn insert_sense(senses: &mut Vec<Sense>, path: &[&str], labels: Vec<String>) {
+    let [parents @ .., gloss] = path else {
+        return;
+    };
+    let mut siblings = senses;
+    for parent in parents {
+        let i = match siblings.iter().position(|s| s.gloss == *parent) {
+            Some(i) => i,
+            None => {
+                siblings.push(Sense {
+                    gloss: parent.to_string(),
+                    ..Sense::default()
+                });
+                siblings.len() - 1
+            }
+        };
+        siblings = &mut siblings[i].senses;
+    }
+    siblings.push(Sense {
+        gloss: gloss.to_string(),
+        labels,
+        senses: vec![],
+    });
+}
+
+/// `noun` becomes `Noun`, like the headings of a page.
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// One etymology per etymology number, from the first line that has one.
+fn etymologies(lines: &[Value]) -> Vec<etymology::Data> {
+    let mut numbers = Vec::<u64>::new();
+    let mut etymologies = vec![];
+    for line in lines {
+        let number = line
+            .get("etymology_number")
+            .and_then(Value::as_u64)
+            .unwrap_or(0);
+        let text = line
+            .get("etymology_text")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let templates = line
+            .get("etymology_templates")
+            .and_then(Value::as_array)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        if numbers.contains(&number) || (text.is_empty() && templates.is_empty()) {
+            continue;
+        }
+        numbers.push(number);
+
+        let title = match number {
+            0 => "Etymology".to_string(),
+            n => format!("Etymology {}", n),
+        };
+        let mut data = etymology::from_text(&title, text);
+        for template in templates {
+            let Some(name) = template.get("name").and_then(Value::as_str) else {
+                continue;
+            };
+            etymology::add_template(&mut data, name, |param| {
+                let s = template.get("args")?.get(param)?.as_str()?;
+                (!s.is_empty()).then(|| s.to_string())
+            });
+        }
+        etymologies.push(data);
+    }
+    etymologies
+}
+
+/// Reads the arguments of the `{{Han char}}` head template of a Translingual line.
+fn han_char(line: &Value) -> Option<translingual::Data> {
+    let args = template(&[line], "Han char")?;
+    Some(translingual::from_params(|name| arg(args, name)))
+}
+
+/// The lines in the language `code`, or `None` if there are none.
+fn language<'a>(lines: &'a [Value], code: &str) -> Option<Vec<&'a Value>> {
+    let lines = lines
+        .iter()
+        .filter(|line| line.get("lang_code").and_then(Value::as_str) == Some(code))
+        .collect::<Vec<_>>();
+    (!lines.is_empty()).then_some(lines)
+}
+
+/// The arguments of the first `name` template among the head and etymology templates of
+/// `lines`.
+fn template<'a>(lines: &[&'a Value], name: &str) -> Option<&'a Value> {
+    lines
+        .iter()
+        .flat_map(|line| ["head_templates", "etymology_templates"].map(|key| line.get(key)))
+        .flatten()
+        .filter_map(Value::as_array)
+        .flatten()
+        .find(|t| t.get("name").and_then(Value::as_str) == Some(name))?
+        .get("args")
+}
+
+fn arg(args: &Value, name: &str) -> Option<String> {
+    let s = args.get(name)?.as_str()?;
+    (!s.is_empty()).then(|| s.to_string())
+}
+
+fn sounds(line: &Value) -> Vec<Sound<'_>> {
+    let Some(sounds) = line.get("sounds").and_then(Value::as_array) else {
+        return vec![];
+    };
+
+    sounds
+        .iter()
+        .filter_map(|sound| {
+            let pron = sound
+                .get("zh_pron")
+                .or_else(|| sound.get("zh-pron"))?
+                .as_str()?;
+            let tags = ["tags", "raw_tags"]
+                .into_iter()
+                .filter_map(|key| sound.get(key)?.as_array())
+                .flatten()
+                .filter_map(Value::as_str)
+                .map(normalize_tag)
+                .collect();
+            Some(Sound { pron, tags })
+        })
+        .collect()
+}
+
+/// `Baxter-Sagart`, `Baxter–Sagart` and `baxter sagart` all become `baxtersagart`.
+fn normalize_tag(tag: &str) -> String {
+    tag.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Adds the characters a simplified or variant entry points to.
+fn push_redirects(redirects: &mut Vec<Redirect>, c: char, line: &Value) {
+    let Some(senses) = line.get("senses").and_then(Value::as_array) else {
+        return;
+    };
+    for sense in senses {
+        let glossed = sense
+            .get("glosses")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .filter_map(|gloss| gloss.split_once(" – see ").map(|(_, rest)| rest));
+        for tail in glossed {
+            push_see_targets(redirects, tail);
+        }
+
+        // NOTE: The glosses carry the senses, so links only add what they missed.
+        let tags = sense
+            .get("tags")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .map(normalize_tag)
+            .collect::<Vec<_>>();
+        for key in ["alt_of", "form_of"] {
+            let relation = if tags.iter().any(|t| t == "simplified") {
+                Relation::Simplified
+            } else if tags.iter().any(|t| t == "shinjitai") {
+                Relation::Shinjitai
+            } else if key == "alt_of" {
+                Relation::Variant
+            } else {
+                Relation::Simplified
+            };
+            let links = sense
+                .get(key)
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten();
+            for link in links {
+                let Some(t) = link
+                    .get("word")
+                    .and_then(Value::as_str)
+                    .and_then(|w| w.chars().next())
+                else {
+                    continue;
+                };
+                if !redirects.iter().any(|r| r.c == t) {
+                    redirects.push(Redirect {
+                        c: t,
+                        sense: link
+                            .get("extra")
+                            .and_then(Value::as_str)
+                            .map(str::to_string),
+                        relation,
+                    });
+                }
+            }
+        }
+    }
+    redirects.retain(|r| r.c != c);
+}
+
+fn pronunciation(sounds: &[Sound]) -> Pronunciation {
+    let mut pron = Pronunciation::default();
+    let mut params = HashMap::<&str, Vec<&str>>::new();
+    let mut hakka = Vec::<(&str, &str)>::new();
+
+    for sound in sounds {
+        if sound.has("middlechinese") {
+            // NOTE: wiktextract only keeps the transcription of the rime table. The other
+            // fields are reported as missing when shown.
+            pron.middle.push(middle::Data {
+                baxter: sound.pron.to_string(),
+                ..middle::Data::default()
+            });
+        } else if sound.has("oldchinese") {
+            let old_chinese = sound.pron.trim_matches('/').to_string();
+            if sound.has("baxtersagart") {
+                pron.old_bs.push(old_bs::Data {
+                    old_chinese,
+                    ..old_bs::Data::default()
+                });
+            } else if sound.has("zhengzhang") {
+                pron.old_zh.push(old_zh::Data {
+                    old_chinese,
+                    ..old_zh::Data::default()
+                });
+            }
+        } else if sound.has("hakka") {
+            let name = if sound.has("hailu") {
+                "hrs"
+            } else if sound.has("meixian") {
+                "gd"
+            } else if sound.has("changting") {
+                "ct"
+            } else {
+                "pfs"
+            };
+            if !hakka.iter().any(|(n, _)| *n == name) {
+                hakka.push((name, sound.pron));
+            }
+        } else if let Some(name) = param_name(sound) {
+            let values = params.entry(name).or_default();
+            if !values.contains(&sound.pron) {
+                values.push(sound.pron);
+            }
+        }
+    }
+
+    number_readings(&mut pron.middle, |d| &mut d.reading);
+    number_readings(&mut pron.old_bs, |d| &mut d.reading);
+    number_readings(&mut pron.old_zh, |d| &mut d.reading);
+
+    let mut params = params
+        .into_iter()
+        .map(|(name, values)| (name, values.join(",")))
+        .collect::<HashMap<_, _>>();
+    if !hakka.is_empty() {
+        let hakka = hakka
+            .into_iter()
+            .map(|(name, pron)| format!("{}={}", name, pron))
+            .collect::<Vec<_>>()
+            .join(";");
+        params.insert("h", hakka);
+    }
+    pron.modern = modern::from_params(|name| params.get(name).cloned());
+
+    pron
+}
+
+/// Maps the tags of a sound to the `{{zh-pron}}` parameter it would have come from.
+fn param_name(sound: &Sound) -> Option<&'static str> {
+    if sound.has("mandarin") {
+        if sound.has_any(&["chengdu", "sichuanese"]) {
+            Some("m-s")
+        } else if sound.has_any(&["xian", "guanzhong"]) {
+            Some("m-x")
+        } else if sound.has("nanjing") {
+            Some("m-nj")
+        } else if sound.has("dungan") {
+            sound.has("cyrillic").then_some("dg")
+        } else {
+            sound.has("pinyin").then_some("m")
+        }
+    } else if sound.has("dungan") {
+        sound.has("cyrillic").then_some("dg")
+    } else if sound.has("cantonese") {
+        if sound.has("dongguan") {
+            Some("c-dg")
+        } else if sound.has_any(&["taishan", "taishanese"]) {
+            Some("c-t")
+        } else if sound.has("yangjiang") {
+            Some("c-yj")
+        } else {
+            sound.has("jyutping").then_some("c")
+        }
+    } else if sound.has("gan") {
+        Some("g")
+    } else if sound.has("jin") {
+        Some("j")
+    } else if sound.has_any(&["northernmin", "jianou"]) {
+        Some("mb")
+    } else if sound.has_any(&["easternmin", "fuzhou"]) {
+        Some("md")
+    } else if sound.has_any(&["puxianmin", "putian", "xianyou"]) {
+        Some("px")
+    } else if sound.has("teochew") {
+        Some("mn-t")
+    } else if sound.has("leizhou") {
+        Some("mn-l")
+    } else if sound.has_any(&["hokkien", "southernmin"]) {
+        Some("mn")
+    } else if sound.has_any(&["southernpinghua", "pinghua"]) {
+        Some("sp")
+    } else if sound.has("wu") {
+        if sound.has("jinhua") {
+            Some("w-j")
+        } else {
+            Some("w")
+        }
+    } else if sound.has("xiang") {
+        if sound.has("loudi") {
+            Some("x-l")
+        } else if sound.has("hengyang") {
+            Some("x-h")
+        } else {
+            Some("x")
+        }
+    } else {
+        None
+    }
+}
+
+fn number_readings<T>(datas: &mut [T], reading: impl Fn(&mut T) -> &mut String) {
+    let len = datas.len();
+    for (i, data) in datas.iter_mut().enumerate() {
+        *reading(data) = format!("{}/{}", i + 1, len);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, fs};
+
+    fn line(word: char, gloss: &str) -> String {
+        serde_json::json!({
+            "word": word.to_string(),
+            "lang_code": "zh",
+            "senses": [{ "glosses": [gloss] }],
+        })
+        .to_string()
+    }
+
+    /// Looks `chars` up in a dump of `lines` named after the test.
+    fn lookup_lines(
+        test: &str,
+        lines: &[String],
+        chars: &[char],
+        depth: usize,
+    ) -> HashMap<char, Option<Entry>> {
+        let path = env::temp_dir().join(format!("wangyun-{}-{}.jsonl", test, std::process::id()));
+        fs::write(&path, lines.join("\n")).unwrap();
+        let entries = lookup(&path, chars, depth);
+        fs::remove_file(&path).unwrap();
+        entries.unwrap()
+    }
+
+    #[test]
+    fn reads_redirect_targets() {
+        let lines = [
+            line('發', "to send out"),
+            line('发', "simplified form of 發 – see 發 and 髮"),
+            line('髮', "hair"),
+            line('乾', "simplified form of 干 – see 干"),
+            line('干', "shield"),
+        ];
+        let entries = lookup_lines("redirects", &lines, &['发', '字'], 1);
+        let mut found = entries
+            .iter()
+            .filter(|(_, entry)| entry.is_some())
+            .map(|(c, _)| *c)
+            .collect::<Vec<_>>();
+        found.sort();
+        assert_eq!(found, ['发', '發', '髮']);
+        assert!(entries[&'字'].is_none());
+        assert!(!entries.contains_key(&'干'));
+    }
+
+    #[test]
+    fn reads_other_languages() {
+        let ja = serde_json::json!({
+            "word": "行",
+            "lang_code": "ja",
+            "head_templates": [{
+                "name": "ja-readings",
+                "args": { "goon": "ぎょう<ぎやう", "kun": "いく" },
+            }],
+        });
+        let ko = serde_json::json!({
+            "word": "行",
+            "lang_code": "ko",
+            "head_templates": [{ "name": "ko-hanja", "args": { "1": "다닐", "2": "행" } }],
+        });
+        let vi = serde_json::json!({
+            "word": "行",
+            "lang_code": "vi",
+            "head_templates": [{ "name": "vi-hantu", "args": { "1": "hành" } }],
+        });
+        let lines = [
+            line('行', "to walk"),
+            ja.to_string(),
+            ko.to_string(),
+            vi.to_string(),
+        ];
+        let entries = lookup_lines("languages", &lines, &['行'], 0);
+
+        let entry = entries[&'行'].as_ref().unwrap();
+        let japanese = entry.japanese.as_ref().unwrap();
+        assert_eq!(japanese.go_on[0].kana, "ぎょう");
+        assert_eq!(japanese.go_on[0].historical.as_deref(), Some("ぎやう"));
+        assert_eq!(japanese.kun[0].kana, "いく");
+        let korean = entry.korean.as_ref().unwrap();
+        assert_eq!(korean.eumhun[0].hun, "다닐");
+        assert_eq!(korean.eumhun[0].eum, "행");
+        let vietnamese = entry.vietnamese.as_ref().unwrap();
+        assert_eq!(vietnamese.han_viet[0].text, "hành");
+        assert!(vietnamese.nom.is_empty());
+    }
+}
@@ -0,0 +1,210 @@
+use crate::{
+    regexes::regexes,
+    utils::{param, template_params},
+    wikitext,
+};
+
+/// A Sino-Korean reading.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Reading {
+    pub hangul: String,
+    /// Revised Romanization.
+    pub revised: String,
+    pub yale: String,
+}
+
+/// A gloss and reading pair (음훈), e.g. `글자 자`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Eumhun {
+    /// The native word that glosses the character (훈).
+    pub hun: String,
+    /// The reading (음).
+    pub eum: String,
+}
+
+/// The readings of `{{ko-hanja}}` in the Korean section.
+#[derive(Debug, Clone, Default)]
+pub struct Data {
+    pub readings: Vec<Reading>,
+    pub eumhun: Vec<Eumhun>,
+    pub middle_korean: Vec<String>,
+}
+
+impl Data {
+    pub fn is_empty(&self) -> bool {
+        self.readings.is_empty() && self.eumhun.is_empty() && self.middle_korean.is_empty()
+    }
+}
+
+/// Reads the parameters of `{{ko-hanja}}` from the `data-mw` of a Parsoid HTML section, and the
+/// Middle Korean readings from its text.
+pub fn fetch(section: &str) -> Data {
+    let mut data = match template_params(section, "ko-hanja") {
+        Some(params) => from_params(|name| param(&params, name)),
+        None => Data::default(),
+    };
+    // NOTE: Middle Korean readings are usually looked up by the module rather than written in
+    // the template, so they only show up in the rendered text.
+    let text = regexes().html_tag.replace_all(section, "");
+    for caps in regexes().middle_korean.captures_iter(&text) {
+        push(&mut data.middle_korean, caps[1].to_string());
+    }
+    data
+}
+
+/// Reads `{{ko-hanja}}` from the Korean section of a wikitext page.
+pub fn from_wikitext(section: &str) -> Data {
+    match wikitext::templates(section, "ko-hanja").first() {
+        Some(hanja) => from_params(|name| hanja.get(name).map(str::to_string)),
+        None => Data::default(),
+    }
+}
+
+/// Builds the data from the parameters of `{{ko-hanja}}`: either `eumhun=글자 자` or pairs of
+/// positional parameters `|글자|자`.
+pub fn from_params(get: impl Fn(&str) -> Option<String>) -> Data {
+    let mut data = Data::default();
+
+    let mut pairs = vec![];
+    if let Some(eumhun) = get("eumhun") {
+        for eumhun in eumhun.split([',', ';']) {
+            let eumhun = wikitext::plain(eumhun);
+            if let Some((hun, eum)) = eumhun.rsplit_once(' ') {
+                pairs.push((hun.to_string(), eum.to_string()));
+            }
+        }
+    }
+    for i in (1..).step_by(2) {
+        let (Some(hun), Some(eum)) = (get(&i.to_string()), get(&(i + 1).to_string())) else {
+            break;
+        };
+        pairs.push((wikitext::plain(&hun), wikitext::plain(&eum)));
+    }
+
+    for (hun, eum) in pairs {
+        let reading = Reading {
+            revised: romanize(&eum, revised),
+            yale: romanize(&eum, yale),
+            hangul: eum.clone(),
+        };
+        if !data.readings.contains(&reading) {
+            data.readings.push(reading);
+        }
+        let eumhun = Eumhun { hun, eum };
+        if !data.eumhun.contains(&eumhun) {
+            data.eumhun.push(eumhun);
+        }
+    }
+
+    if let Some(mk) = get("mk") {
+        for mk in mk.split(',') {
+            push(&mut data.middle_korean, wikitext::plain(mk));
+        }
+    }
+
+    data
+}
+
+fn push(values: &mut Vec<String>, value: String) {
+    if !value.is_empty() && !values.contains(&value) {
+        values.push(value);
+    }
+}
+
+/// Romanizes each syllable of `hangul` on its own, which is what a Sino-Korean reading is.
+/// Anything that is not a precomposed syllable is kept as it is.
+fn romanize(hangul: &str, system: fn(usize, usize, usize) -> String) -> String {
+    hangul
+        .chars()
+        .map(|c| match (c as u32).checked_sub(0xAC00) {
+            Some(index) if index < 11172 => {
+                let index = index as usize;
+                system(index / (21 * 28), index / 28 % 21, index % 28)
+            }
+            _ => c.to_string(),
+        })
+        .collect()
+}
+
+fn revised(initial: usize, medial: usize, fin: usize) -> String {
+    const INITIALS: [&str; 19] = [
+        "g", "kk", "n", "d", "tt", "r", "m", "b", "pp", "s", "ss", "", "j", "jj", "ch", "k", "t",
+        "p", "h",
+    ];
+    const MEDIALS: [&str; 21] = [
+        "a", "ae", "ya", "yae", "eo", "e", "yeo", "ye", "o", "wa", "wae", "oe", "yo", "u", "wo",
+        "we", "wi", "yu", "eu", "ui", "i",
+    ];
+    // NOTE: A final is only pronounced as one of seven sounds before a pause.
+    const FINALS: [&str; 28] = [
+        "", "k", "k", "k", "n", "n", "n", "t", "l", "k", "m", "l", "l", "l", "p", "l", "m", "p",
+        "p", "t", "t", "ng", "t", "t", "k", "t", "p", "t",
+    ];
+    format!("{}{}{}", INITIALS[initial], MEDIALS[medial], FINALS[fin])
+}
+
+fn yale(initial: usize, medial: usize, fin: usize) -> String {
+    const INITIALS: [&str; 19] = [
+        "k", "kk", "n", "t", "tt", "l", "m", "p", "pp", "s", "ss", "", "c", "cc", "ch", "kh", "th",
+        "ph", "h",
+    ];
+    const MEDIALS: [&str; 21] = [
+        "a", "ay", "ya", "yay", "e", "ey", "ye", "yey", "o", "wa", "way", "oy", "yo", "wu", "we",
+        "wey", "wi", "yu", "u", "uy", "i",
+    ];
+    const FINALS: [&str; 28] = [
+        "", "k", "kk", "ks", "n", "nc", "nh", "t", "l", "lk", "lm", "lp", "ls", "lth", "lph", "lh",
+        "m", "p", "ps", "s", "ss", "ng", "c", "ch", "kh", "th", "ph", "h",
+    ];
+    // NOTE: `wu` is written `u` after labials, e.g. `pu` for 부.
+    let labial = matches!(INITIALS[initial], "m" | "p" | "pp" | "ph");
+    let medial = match MEDIALS[medial] {
+        "wu" if labial => "u",
+        medial => medial,
+    };
+    format!("{}{}{}", INITIALS[initial], medial, FINALS[fin])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{fixture, isolate_section};
+
+    fn eumhun(hun: &str, eum: &str) -> Eumhun {
+        Eumhun {
+            hun: hun.to_string(),
+            eum: eum.to_string(),
+        }
+    }
+
+    #[test]
+    fn reads_eumhun_and_middle_korean() {
+        let page = fixture('行');
+        let data = fetch(isolate_section(&page, "Korean").expect("no Korean"));
+        assert_eq!(data.eumhun, [eumhun("다닐", "행"), eumhun("항렬", "항")]);
+        let readings = data
+            .readings
+            .iter()
+            .map(|r| (r.hangul.as_str(), r.revised.as_str(), r.yale.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(readings, [("행", "haeng", "hayng"), ("항", "hang", "hang")]);
+        assert_eq!(data.middle_korean, ["ᅘᆡᇰ〮"]);
+    }
+
+    #[test]
+    fn romanizes_syllables() {
+        for (hangul, rr, y) in [
+            ("행", "haeng", "hayng"),
+            ("부", "bu", "pu"),
+            ("자", "ja", "ca"),
+            ("글", "geul", "kul"),
+            ("외", "oe", "oy"),
+            ("학", "hak", "hak"),
+            ("다닐", "danil", "tanil"),
+        ] {
+            assert_eq!(romanize(hangul, revised), rr);
+            assert_eq!(romanize(hangul, yale), y);
+        }
+        assert_eq!(romanize("ᅘᆡᇰ", yale), "ᅘᆡᇰ");
+    }
+}
@@ -1,24 +1,54 @@
 use crate::{
+    error::Error,
     modern::Variants,
-    prog::{baxter, display},
+    prog::{baxter, display, Pick},
 };
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use regexes::regexes;
+use source::Source;
+use std::{path::PathBuf, process::ExitCode};
 
+mod cache;
+mod definitions;
+mod doctor;
+mod dump;
+mod error;
+mod etymology;
+mod fetch;
+mod japanese;
+mod kaikki;
+mod korean;
 mod middle;
 mod modern;
+mod normalize;
 mod old_bs;
 mod old_zh;
 mod prog;
 mod regexes;
 mod request;
+mod sections;
+mod source;
+mod table;
+mod translingual;
 mod utils;
+mod vietnamese;
+mod wikitext;
+mod zim;
 
 #[derive(Debug, Parser)]
-#[command(version, about)]
+#[command(
+    version,
+    about,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Characters to look up
-    chars: String,
+    #[arg(required = true)]
+    chars: Option<String>,
 
     /// Show Middle Chinese rime info
     #[arg(short, long)]
@@ -32,23 +62,147 @@ struct Args {
     #[arg(short('M'), long)]
     modern: Vec<Variants>,
 
+    /// Show the senses of each part of speech with their labels
+    #[arg(short, long)]
+    definitions: bool,
+
+    /// Show the radical, strokes, IDS, Cangjie, four-corner and Unicode data
+    #[arg(short, long)]
+    info: bool,
+
+    /// Show the glyph origin and etymology with their components and reconstructions
+    #[arg(short, long)]
+    etymology: bool,
+
+    /// Show the Sino-Japanese readings and kun'yomi from the Japanese section
+    #[arg(short('J'), long)]
+    japanese: bool,
+
+    /// Show the Sino-Korean readings, eumhun and Middle Korean from the Korean section
+    #[arg(short('K'), long)]
+    korean: bool,
+
+    /// Show the Hán Việt and Nôm readings from the Vietnamese section
+    #[arg(long)]
+    vietnamese: bool,
+
     /// Only print Baxter's Middle Chinese transcription
     #[arg(short, long)]
     baxter: bool,
 
+    /// How `--baxter` shows a simplified character with several traditional forms
+    #[arg(long, value_enum, default_value_t = Pick::All)]
+    pick: Pick,
+
     /// Only print the result
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     quiet: bool,
+
+    /// Where to read entries from: `wiktionary`, `wikitext`, `kaikki:<path>`, `dump:<dir>` or
+    /// `zim:<path>`
+    #[arg(short, long, default_value_t = Source::Wiktionary)]
+    source: Source,
+
+    /// Seconds before a cached page is downloaded again
+    #[arg(long, default_value_t = cache::DEFAULT_TTL, global = true)]
+    ttl: u64,
+
+    /// Number of requests to send at once
+    #[arg(short, long, default_value_t = fetch::DEFAULT_JOBS)]
+    jobs: usize,
+
+    /// Seconds before a request times out
+    #[arg(long, default_value_t = request::DEFAULT_TIMEOUT, global = true)]
+    timeout: u64,
+
+    /// Times a failed request is retried
+    #[arg(long, default_value_t = request::DEFAULT_RETRIES, global = true)]
+    retries: u32,
+
+    /// Only use cached pages and list the ones that are missing
+    #[arg(long, global = true)]
+    offline: bool,
+
+    /// Stop at the first character or table that fails instead of printing the rest
+    #[arg(long)]
+    strict: bool,
+
+    /// Base URL of the REST `page/html` endpoint
+    #[arg(long, default_value = request::DEFAULT_ENDPOINT, global = true)]
+    endpoint: String,
+
+    /// Base URL of the raw wikitext endpoint, followed by the character
+    #[arg(long, default_value = request::DEFAULT_WIKITEXT_ENDPOINT, global = true)]
+    wikitext_endpoint: String,
+
+    /// Write every response to this directory
+    #[arg(long, value_name = "DIR", conflicts_with = "replay", global = true)]
+    record: Option<PathBuf>,
+
+    /// Serve responses from this directory instead of the network
+    #[arg(long, value_name = "DIR", global = true)]
+    replay: Option<PathBuf>,
+}
+
+impl Args {
+    fn input(&self) -> &str {
+        self.chars.as_deref().unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Manage the page cache
+    #[command(subcommand)]
+    Cache(CacheCommand),
+    /// Check that every extractor still understands the layout of Wiktionary pages
+    Doctor,
+    /// Import Chinese pages from a Wikimedia Enterprise HTML dump for `--source dump:<dir>`
+    Import {
+        /// The `.json.tar.gz` dump of en.wiktionary
+        dump: PathBuf,
+        /// Directory to write the pages to
+        out: PathBuf,
+    },
 }
 
-fn main() -> reqwest::Result<()> {
+#[derive(Debug, Subcommand)]
+enum CacheCommand {
+    /// List cached pages
+    List,
+    /// Remove expired pages
+    Prune {
+        /// Remove every page regardless of its age
+        #[arg(short, long)]
+        all: bool,
+    },
+    /// Download cached pages again
+    Refresh {
+        /// Only refresh these characters
+        chars: Option<String>,
+    },
+    /// Show cache statistics
+    Stats,
+}
+
+fn main() -> ExitCode {
     let args = Args::parse();
 
-    if args.baxter {
-        baxter(&args)?;
-    } else {
-        display(&args)?;
-    }
+    let result = match args.command {
+        Some(Command::Cache(ref command)) => cache::command(&args, command),
+        Some(Command::Doctor) => doctor::run(&args),
+        Some(Command::Import { ref dump, ref out }) => {
+            dump::import(&args, dump, out).map_err(|e| Error::io_at(dump, e))
+        }
+        None if args.baxter => baxter(&args),
+        None => display(&args),
+    };
 
-    Ok(())
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            e.exit_code()
+        }
+    }
 }
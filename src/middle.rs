@@ -1,24 +1,50 @@
+use scraper::Html;
 use std::fmt::Display;
 
 use crate::{
-    regexes::regexes,
-    utils::{fetch_row, regex_isolate_one},
+    error::{Error, Result},
+    table::{sel, text, Table},
 };
 
-#[derive(Debug, Default)]
-pub struct Data<'a> {
-    pub reading: &'a str,
-    pub init: &'a str,
-    pub fin: &'a str,
+const TABLE: &str = "Middle Chinese";
+
+#[derive(Debug, Clone, Default)]
+pub struct Data {
+    pub reading: String,
+    pub init: String,
+    pub fin: String,
     pub tone: Tones,
     pub open: bool,
     pub division: usize,
     pub fanqie: String,
-    pub baxter: &'a str,
-    pub expected_mandarin: &'a str,
+    pub baxter: String,
+    pub expected_mandarin: String,
     pub expected_cantonese: String,
 }
 
+impl Data {
+    /// The fields left empty by a source that does not have them. A table has every one.
+    pub fn missing(&self) -> Vec<&'static str> {
+        [
+            ("rime", self.init.is_empty()),
+            ("fanqie", self.fanqie.is_empty()),
+            ("Baxter transcription", self.baxter.is_empty()),
+            (
+                "expected Mandarin reflex",
+                self.expected_mandarin.is_empty(),
+            ),
+            (
+                "expected Cantonese reflex",
+                self.expected_cantonese.is_empty(),
+            ),
+        ]
+        .into_iter()
+        .filter(|(_, missing)| *missing)
+        .map(|(name, _)| name)
+        .collect()
+    }
+}
+
 #[derive(Debug, Copy, Clone, Default)]
 pub enum Tones {
     #[default]
@@ -45,138 +71,173 @@ impl Display for Tones {
     }
 }
 
-impl From<&str> for Tones {
-    fn from(s: &str) -> Self {
+impl TryFrom<&str> for Tones {
+    type Error = Error;
+
+    fn try_from(s: &str) -> Result<Self> {
         match s {
-            "Level" => Self::Ping,
-            "Rising" => Self::Shang,
-            "Departing" => Self::Qu,
-            "Checked" => Self::Ru,
-            _ => unreachable!("Invalid tone name."),
+            "Level" => Ok(Self::Ping),
+            "Rising" => Ok(Self::Shang),
+            "Departing" => Ok(Self::Qu),
+            "Checked" => Ok(Self::Ru),
+            _ => Err(Error::malformed(TABLE, "Tone", s)),
         }
     }
 }
 
-pub fn fetch(section: &'_ str) -> Vec<Data<'_>> {
-    let re_start = &regexes().mc_section_start;
-    let re_end = &regexes().mc_section_end;
-    let mc_section = if let Some(s) = regex_isolate_one(section, re_start, re_end) {
-        s
-    } else {
-        return vec![];
+pub fn fetch(section: &str) -> Result<Vec<Data>> {
+    let doc = Html::parse_fragment(section);
+    let Some(table) = Table::find(&doc, TABLE, TABLE) else {
+        return Ok(vec![]);
     };
 
     let mut datas = vec![];
 
-    let readings = fetch_row(
-        mc_section,
-        &regexes().mc_reading_start,
-        &regexes().mc_simple_row,
-    );
+    let readings = table.row_text("Reading #");
+    if readings.is_empty() {
+        return Err(Error::malformed(TABLE, "Reading #", ""));
+    }
     for reading in readings {
         datas.push(Data {
             reading,
             ..Data::default()
         });
     }
+    let len = datas.len();
 
-    let inits = fetch_row(mc_section, &regexes().mc_init_start, &regexes().mc_init_fin);
-    for (data, init) in datas.iter_mut().zip(inits.iter()) {
+    let inits = table.row_text_exact("Initial", len)?;
+    for (data, init) in datas.iter_mut().zip(inits) {
         data.init = init;
     }
 
-    let fins = fetch_row(mc_section, &regexes().mc_fin_start, &regexes().mc_init_fin);
-    for (data, fin) in datas.iter_mut().zip(fins.iter()) {
+    let fins = table.row_text_exact("Final", len)?;
+    for (data, fin) in datas.iter_mut().zip(fins) {
         data.fin = fin;
     }
 
-    let tones = fetch_row(mc_section, &regexes().mc_tone_start, &regexes().mc_tone);
-    for (data, tone) in datas.iter_mut().zip(tones.iter()) {
-        data.tone = (*tone).into();
+    // NOTE: The tone cell also has the tone letter, e.g. `Departing (H)`.
+    let tones = table.row_text_exact("Tone", len)?;
+    for (data, tone) in datas.iter_mut().zip(tones) {
+        data.tone = tone
+            .split_whitespace()
+            .next()
+            .unwrap_or_default()
+            .try_into()?;
     }
 
-    let opens = fetch_row(
-        mc_section,
-        &regexes().mc_open_start,
-        &regexes().mc_simple_row,
-    );
-    for (data, open) in datas.iter_mut().zip(opens.iter()) {
-        data.open = match *open {
+    let opens = table.row_text_exact("Openness", len)?;
+    for (data, open) in datas.iter_mut().zip(opens) {
+        data.open = match open.as_str() {
             "Open" => true,
             "Closed" => false,
-            _ => unreachable!("Invalid openness `{}`", *open),
+            _ => return Err(Error::malformed(TABLE, "Openness", open)),
         }
     }
 
-    let divs = fetch_row(
-        mc_section,
-        &regexes().mc_div_start,
-        &regexes().mc_simple_row,
-    );
-    for (data, div) in datas.iter_mut().zip(divs.iter()) {
-        data.division = match *div {
+    let divs = table.row_text_exact("Division", len)?;
+    for (data, div) in datas.iter_mut().zip(divs) {
+        data.division = match div.as_str() {
             "I" => 1,
             "II" => 2,
             "III" => 3,
             "IV" => 4,
-            _ => unreachable!("Invalid division `{}`", *div),
+            _ => return Err(Error::malformed(TABLE, "Division", div)),
         }
     }
 
-    let fanqies = fetch_fanqie(mc_section);
-    for (data, fanqie) in datas.iter_mut().zip(fanqies.into_iter()) {
-        data.fanqie = fanqie;
+    let fanqies = table.row_exact("Fanqie", len)?;
+    for (data, fanqie) in datas.iter_mut().zip(fanqies) {
+        let mut elem = fanqie.select(&sel("a")).map(text).collect::<String>();
+        if elem.is_empty() {
+            elem = text(fanqie);
+        }
+        elem.push('切');
+        data.fanqie = elem;
     }
 
-    let baxters = fetch_row(mc_section, &regexes().mc_baxter_start, &regexes().mc_baxter);
-    for (data, baxter) in datas.iter_mut().zip(baxters.iter()) {
+    let baxters = table.row_text_exact("Baxter", len)?;
+    for (data, baxter) in datas.iter_mut().zip(baxters) {
         data.baxter = baxter;
     }
 
-    let expected_mandarins = fetch_row(
-        mc_section,
-        &regexes().mc_mandarin_start,
-        &regexes().mc_mandarin,
-    );
-    for (data, expected_mandarin) in datas.iter_mut().zip(expected_mandarins.iter()) {
+    let expected_mandarins = table.row_text_exact("Expected Mandarin Reflex", len)?;
+    for (data, expected_mandarin) in datas.iter_mut().zip(expected_mandarins) {
         data.expected_mandarin = expected_mandarin;
     }
 
-    let expected_cantoneses = fetch_row(
-        mc_section,
-        &regexes().mc_cantonese_start,
-        &regexes().mc_cantonese,
-    );
-    for (data, expected_cantonese) in datas.iter_mut().zip(expected_cantoneses.iter()) {
-        let expected = expected_cantonese
-            .replace("<sup>", "")
-            .replace("</sup>", "");
-        data.expected_cantonese = expected;
+    let expected_cantoneses = table.row_text_exact("Expected Cantonese Reflex", len)?;
+    for (data, expected_cantonese) in datas.iter_mut().zip(expected_cantoneses) {
+        data.expected_cantonese = expected_cantonese;
     }
 
-    datas
+    Ok(datas)
 }
 
-pub fn fetch_fanqie(section: &str) -> Vec<String> {
-    let re_row_start = &regexes().mc_fanqie_start;
-    let re_row_end = &regexes().row_end;
-    let re_row_elem = &regexes().mc_fanqie;
-    let mut elems = Vec::<String>::new();
-    if let Some(row) = regex_isolate_one(section, re_row_start, re_row_end) {
-        for (_, [f1, f2]) in re_row_elem.captures_iter(row).map(|c| c.extract()) {
-            let mut elem = String::new();
-            elem.push_str(f1);
-            elem.push_str(f2);
-            elem.push('切');
-            elems.push(elem);
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{fixture, fixture_pronunciations, isolate_chinese_section};
+
+    #[test]
+    fn reads_one_reading() {
+        let datas = fetch(&fixture_pronunciations('字')[0]).expect("malformed");
+        assert_eq!(datas.len(), 1);
+        let data = &datas[0];
+        assert_eq!(data.reading, "1/1");
+        assert_eq!(data.init, "從");
+        assert_eq!(data.fin, "之");
+        assert_eq!(data.tone.to_string(), "去");
+        assert!(data.open);
+        assert_eq!(data.division, 3);
+        assert_eq!(data.fanqie, "疾置切");
+        assert_eq!(data.baxter, "dziH");
+        assert_eq!(data.expected_mandarin, "zì");
+        assert_eq!(data.expected_cantonese, "zi6");
+        assert!(data.missing().is_empty());
+    }
 
-        if elems.is_empty() {
-            unreachable!("Misformatted HTML: Row should be filled.");
-        }
-    } else {
-        unreachable!("Misformatted HTML: Middle Chinese section should have the specified row");
+    #[test]
+    fn lists_missing_fields() {
+        let data = Data {
+            reading: "1/1".to_string(),
+            baxter: "dziH".to_string(),
+            ..Data::default()
+        };
+        assert_eq!(
+            data.missing(),
+            [
+                "rime",
+                "fanqie",
+                "expected Mandarin reflex",
+                "expected Cantonese reflex"
+            ]
+        );
     }
 
-    elems
+    #[test]
+    fn reads_every_reading() {
+        let pronunciations = fixture_pronunciations('行');
+        let first = fetch(&pronunciations[0]).expect("malformed");
+        let second = fetch(&pronunciations[1]).expect("malformed");
+
+        let baxters = |datas: &[Data]| datas.iter().map(|d| d.baxter.clone()).collect::<Vec<_>>();
+        assert_eq!(baxters(&first), ["haeng", "haengH"]);
+        assert_eq!(baxters(&second), ["hang", "hangH"]);
+
+        assert_eq!(first[1].reading, "2/2");
+        assert_eq!(first[1].tone.to_string(), "去");
+        assert_eq!(first[1].fanqie, "下更切");
+        assert_eq!(first[1].division, 2);
+        assert_eq!(second[0].tone.to_string(), "平");
+        assert_eq!(second[0].fin, "唐");
+        assert_eq!(second[0].division, 1);
+        assert_eq!(second[1].expected_cantonese, "hong6");
+    }
+
+    #[test]
+    fn has_no_table() {
+        let page = fixture('发');
+        let section = isolate_chinese_section(&page).expect("no Chinese");
+        assert!(fetch(section).expect("malformed").is_empty());
+    }
 }
@@ -1,4 +1,4 @@
-use crate::regexes::regexes;
+use crate::{error::Result, normalize::decode_entities, regexes::regexes};
 use clap::ValueEnum;
 use serde_json::{self, Value};
 
@@ -75,7 +75,7 @@ pub enum Variants {
     Xh,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct Data {
     // Mandarin
     pub ma_standard: Option<String>,
@@ -115,61 +115,106 @@ pub struct Data {
     pub xi_hengyang: Option<String>,
 }
 
-pub fn fetch(section: &str) -> Data {
+impl Data {
+    /// Every field with its name.
+    pub fn fields(&self) -> [(&'static str, &Option<String>); 27] {
+        [
+            ("ma_standard", &self.ma_standard),
+            ("ma_chengdu", &self.ma_chengdu),
+            ("ma_xian", &self.ma_xian),
+            ("ma_nanjing", &self.ma_nanjing),
+            ("ma_dungan", &self.ma_dungan),
+            ("ca_guangzhou", &self.ca_guangzhou),
+            ("ca_dongguan", &self.ca_dongguan),
+            ("ca_taishan", &self.ca_taishan),
+            ("ca_yangjiang", &self.ca_yangjiang),
+            ("gan", &self.gan),
+            ("ha_sixian", &self.ha_sixian),
+            ("ha_hailu", &self.ha_hailu),
+            ("ha_meixian", &self.ha_meixian),
+            ("ha_changting", &self.ha_changting),
+            ("jin", &self.jin),
+            ("mi_northern", &self.mi_northern),
+            ("mi_eastern", &self.mi_eastern),
+            ("mi_puxian", &self.mi_puxian),
+            ("mi_hokkien", &self.mi_hokkien),
+            ("mi_teochew", &self.mi_teochew),
+            ("mi_leizhou", &self.mi_leizhou),
+            ("ph_southern", &self.ph_southern),
+            ("wu_northern", &self.wu_northern),
+            ("wu_jinhua", &self.wu_jinhua),
+            ("xi_changsa", &self.xi_changsa),
+            ("xi_loudi", &self.xi_loudi),
+            ("xi_hengyang", &self.xi_hengyang),
+        ]
+    }
+}
+
+pub fn fetch(section: &str) -> Result<Data> {
     let re = &regexes().modern_data;
 
     let mut data = Data::default();
 
     if let Some(caps) = re.captures(section) {
         if let Some(data_str) = caps.get(1) {
-            let wik_data: Value =
-                serde_json::from_str(data_str.as_str()).expect("Invalid JSON data.");
+            // NOTE: The JSON is escaped as an HTML attribute.
+            let wik_data: Value = serde_json::from_str(&decode_entities(data_str.as_str()))?;
             if let Some(prons) = get(&wik_data) {
-                // NOTE: Sometimes the data is wrong and we can do nothing about it.
-                data.ma_standard = get_pron(prons, "m");
-                data.ma_chengdu = get_pron(prons, "m-s");
-                data.ma_xian = get_pron(prons, "m-x");
-                data.ma_nanjing = get_pron(prons, "m-nj");
-                data.ma_dungan = get_pron(prons, "dg");
-                data.ca_guangzhou = get_pron(prons, "c");
-                data.ca_dongguan = get_pron(prons, "c-dg");
-                data.ca_taishan = get_pron(prons, "c-t");
-                data.ca_yangjiang = get_pron(prons, "c-yj");
-                data.gan = get_pron(prons, "g");
-                if let Some(hakka) = get_pron(prons, "h") {
-                    for mut sp in hakka.split(';').map(|s| s.split('=')) {
-                        if let (Some(name), Some(lit)) = (sp.next(), sp.next()) {
-                            let lit_raw = lit.to_string();
-                            let lit_stripped = lit_raw.strip_prefix("h:").map(|s| s.to_string());
-                            let lit = lit_stripped.or(Some(lit_raw));
-                            match name {
-                                "pfs" => data.ha_sixian = lit,
-                                "hrs" => data.ha_hailu = lit,
-                                "gd" => data.ha_meixian = lit,
-                                "ct" => data.ha_changting = lit,
-                                _ => {}
-                            }
-                        };
-                    }
-                }
-                data.jin = get_pron(prons, "j");
-                data.mi_northern = get_pron(prons, "mb");
-                data.mi_eastern = get_pron(prons, "md");
-                data.mi_hokkien = get_pron(prons, "mn").map(|h| simplify_romanizations(&h, '/'));
-                data.mi_teochew = get_pron(prons, "mn-t");
-                data.mi_leizhou = get_pron(prons, "mn-l");
-                data.mi_puxian = get_pron(prons, "px").map(|p| simplify_romanizations(&p, '/'));
-                data.ph_southern = get_pron(prons, "sp");
-                data.wu_northern = get_pron(prons, "w").map(|w| simplify_romanizations(&w, ';'));
-                data.wu_jinhua = get_pron(prons, "w-j");
-                data.xi_changsa = get_pron(prons, "x");
-                data.xi_loudi = get_pron(prons, "x-l");
-                data.xi_hengyang = get_pron(prons, "x-h");
+                data = from_params(|name| get_pron(prons, name));
             }
         }
     }
 
-    data
+    Ok(data)
+}
+
+/// Builds the data from the parameters of `{{zh-pron}}`.
+pub fn from_params(get_pron: impl Fn(&str) -> Option<String>) -> Data {
+    let mut hakka = Data::default();
+    if let Some(h) = get_pron("h") {
+        for mut sp in h.split(';').map(|s| s.split('=')) {
+            if let (Some(name), Some(lit)) = (sp.next(), sp.next()) {
+                let lit_raw = lit.to_string();
+                let lit_stripped = lit_raw.strip_prefix("h:").map(|s| s.to_string());
+                let lit = lit_stripped.or(Some(lit_raw));
+                match name {
+                    "pfs" => hakka.ha_sixian = lit,
+                    "hrs" => hakka.ha_hailu = lit,
+                    "gd" => hakka.ha_meixian = lit,
+                    "ct" => hakka.ha_changting = lit,
+                    _ => {}
+                }
+            };
+        }
+    }
+
+    // NOTE: Sometimes the data is wrong and we can do nothing about it.
+    Data {
+        ma_standard: get_pron("m"),
+        ma_chengdu: get_pron("m-s"),
+        ma_xian: get_pron("m-x"),
+        ma_nanjing: get_pron("m-nj"),
+        ma_dungan: get_pron("dg"),
+        ca_guangzhou: get_pron("c"),
+        ca_dongguan: get_pron("c-dg"),
+        ca_taishan: get_pron("c-t"),
+        ca_yangjiang: get_pron("c-yj"),
+        gan: get_pron("g"),
+        jin: get_pron("j"),
+        mi_northern: get_pron("mb"),
+        mi_eastern: get_pron("md"),
+        mi_hokkien: get_pron("mn").map(|h| simplify_romanizations(&h, '/')),
+        mi_teochew: get_pron("mn-t"),
+        mi_leizhou: get_pron("mn-l"),
+        mi_puxian: get_pron("px").map(|p| simplify_romanizations(&p, '/')),
+        ph_southern: get_pron("sp"),
+        wu_northern: get_pron("w").map(|w| simplify_romanizations(&w, ';')),
+        wu_jinhua: get_pron("w-j"),
+        xi_changsa: get_pron("x"),
+        xi_loudi: get_pron("x-l"),
+        xi_hengyang: get_pron("x-h"),
+        ..hakka
+    }
 }
 
 fn get(data: &Value) -> Option<&Value> {
@@ -0,0 +1,327 @@
+use std::borrow::Cow;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::{
+    definitions::{Definitions, Sense},
+    etymology, japanese, korean, middle, modern, old_bs, old_zh, regexes, translingual, vietnamese,
+};
+
+/// Turns a string extracted from any source into plain text: HTML entities are decoded,
+/// combining diacritics are composed (NFC) and whitespace is collapsed.
+///
+/// Tags are left alone, as `<` is part of some readings, e.g. the Baxter–Sagart `*[k]ˤ<r>uŋ-s`.
+pub fn normalize(s: &str) -> String {
+    decode_entities(s)
+        .nfc()
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The text of a raw HTML slice. Tags are removed before entities are decoded, so an escaped
+/// `&lt;r&gt;` stays in the text.
+pub fn html_text(s: &str) -> String {
+    normalize(&regexes().html_tag.replace_all(s, ""))
+}
+
+pub fn decode_entities(s: &str) -> Cow<'_, str> {
+    regexes()
+        .html_entity
+        .replace_all(s, |caps: &regex::Captures| {
+            decode_entity(&caps[1]).map_or_else(|| caps[0].to_string(), String::from)
+        })
+}
+
+fn decode_entity(name: &str) -> Option<char> {
+    if let Some(hex) = name.strip_prefix("#x").or_else(|| name.strip_prefix("#X")) {
+        return char::from_u32(u32::from_str_radix(hex, 16).ok()?);
+    }
+    if let Some(dec) = name.strip_prefix('#') {
+        return char::from_u32(dec.parse().ok()?);
+    }
+    // NOTE: Parsoid escapes little else. Unknown entities are left as they are.
+    match name {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some('\u{a0}'),
+        "ndash" => Some('–'),
+        "mdash" => Some('—'),
+        "middot" => Some('·'),
+        _ => None,
+    }
+}
+
+/// Splits the input into characters, each with the code points it was typed as. Variation
+/// selectors stay with the character before them, and compatibility ideographs and radicals are
+/// replaced by the unified ideograph they stand for.
+pub fn canonical_chars(input: &str) -> Vec<(String, char)> {
+    let mut chars = Vec::<(String, char)>::new();
+    for c in input.chars() {
+        if is_variation_selector(c) {
+            if let Some((original, _)) = chars.last_mut() {
+                original.push(c);
+                continue;
+            }
+        }
+        chars.push((c.to_string(), canonical(c)));
+    }
+    chars
+}
+
+fn canonical(c: char) -> char {
+    let decomposed = match c {
+        // CJK Radicals Supplement, Kangxi Radicals
+        '\u{2E80}'..='\u{2FDF}' => c.to_string().nfkc().collect::<String>(),
+        // Compatibility Ideographs and their supplement
+        '\u{F900}'..='\u{FAFF}' | '\u{2F800}'..='\u{2FA1F}' => {
+            c.to_string().nfc().collect::<String>()
+        }
+        _ => return c,
+    };
+    let mut chars = decomposed.chars();
+    match (chars.next(), chars.next()) {
+        (Some(unified), None) => unified,
+        _ => c,
+    }
+}
+
+fn is_variation_selector(c: char) -> bool {
+    matches!(c, '\u{FE00}'..='\u{FE0F}' | '\u{E0100}'..='\u{E01EF}')
+}
+
+/// Normalizes every string field of extracted data in place.
+pub trait Normalize {
+    fn normalize(&mut self);
+}
+
+impl Normalize for String {
+    fn normalize(&mut self) {
+        *self = normalize(self);
+    }
+}
+
+impl<T: Normalize> Normalize for Option<T> {
+    fn normalize(&mut self) {
+        if let Some(value) = self {
+            value.normalize();
+        }
+    }
+}
+
+impl<T: Normalize> Normalize for Vec<T> {
+    fn normalize(&mut self) {
+        for value in self.iter_mut() {
+            value.normalize();
+        }
+    }
+}
+
+impl Normalize for Definitions {
+    fn normalize(&mut self) {
+        self.pos.normalize();
+        self.senses.normalize();
+    }
+}
+
+impl Normalize for Sense {
+    fn normalize(&mut self) {
+        self.gloss.normalize();
+        self.labels.normalize();
+        self.senses.normalize();
+    }
+}
+
+impl Normalize for middle::Data {
+    fn normalize(&mut self) {
+        self.reading.normalize();
+        self.init.normalize();
+        self.fin.normalize();
+        self.fanqie.normalize();
+        self.baxter.normalize();
+        self.expected_mandarin.normalize();
+        self.expected_cantonese.normalize();
+    }
+}
+
+impl Normalize for old_bs::Data {
+    fn normalize(&mut self) {
+        self.reading.normalize();
+        self.old_chinese.normalize();
+    }
+}
+
+impl Normalize for old_zh::Data {
+    fn normalize(&mut self) {
+        self.reading.normalize();
+        self.old_chinese.normalize();
+    }
+}
+
+impl Normalize for modern::Data {
+    fn normalize(&mut self) {
+        for field in [
+            &mut self.ma_standard,
+            &mut self.ma_chengdu,
+            &mut self.ma_xian,
+            &mut self.ma_nanjing,
+            &mut self.ma_dungan,
+            &mut self.ca_guangzhou,
+            &mut self.ca_dongguan,
+            &mut self.ca_taishan,
+            &mut self.ca_yangjiang,
+            &mut self.gan,
+            &mut self.ha_sixian,
+            &mut self.ha_hailu,
+            &mut self.ha_meixian,
+            &mut self.ha_changting,
+            &mut self.jin,
+            &mut self.mi_northern,
+            &mut self.mi_eastern,
+            &mut self.mi_puxian,
+            &mut self.mi_hokkien,
+            &mut self.mi_teochew,
+            &mut self.mi_leizhou,
+            &mut self.ph_southern,
+            &mut self.wu_northern,
+            &mut self.wu_jinhua,
+            &mut self.xi_changsa,
+            &mut self.xi_loudi,
+            &mut self.xi_hengyang,
+        ] {
+            field.normalize();
+        }
+    }
+}
+
+impl Normalize for etymology::Data {
+    fn normalize(&mut self) {
+        self.title.normalize();
+        self.text.normalize();
+        self.semantic.normalize();
+        self.phonetic.normalize();
+        self.sino_tibetan.normalize();
+        self.old_chinese.normalize();
+    }
+}
+
+impl Normalize for japanese::Reading {
+    fn normalize(&mut self) {
+        self.kana.normalize();
+        self.historical.normalize();
+    }
+}
+
+impl Normalize for japanese::Data {
+    fn normalize(&mut self) {
+        for field in [
+            &mut self.go_on,
+            &mut self.kan_on,
+            &mut self.to_on,
+            &mut self.kanyo_on,
+            &mut self.on,
+            &mut self.kun,
+        ] {
+            field.normalize();
+        }
+    }
+}
+
+impl Normalize for korean::Reading {
+    fn normalize(&mut self) {
+        self.hangul.normalize();
+        self.revised.normalize();
+        self.yale.normalize();
+    }
+}
+
+impl Normalize for korean::Eumhun {
+    fn normalize(&mut self) {
+        self.hun.normalize();
+        self.eum.normalize();
+    }
+}
+
+impl Normalize for korean::Data {
+    fn normalize(&mut self) {
+        self.readings.normalize();
+        self.eumhun.normalize();
+        self.middle_korean.normalize();
+    }
+}
+
+impl Normalize for vietnamese::Reading {
+    fn normalize(&mut self) {
+        self.text.normalize();
+    }
+}
+
+impl Normalize for vietnamese::Data {
+    fn normalize(&mut self) {
+        self.han_viet.normalize();
+        self.nom.normalize();
+    }
+}
+
+impl Normalize for translingual::Data {
+    fn normalize(&mut self) {
+        for field in [
+            &mut self.radical,
+            &mut self.radical_number,
+            &mut self.additional_strokes,
+            &mut self.strokes,
+            &mut self.ids,
+            &mut self.cangjie,
+            &mut self.four_corner,
+        ] {
+            field.normalize();
+        }
+    }
+}
+
+pub fn normalized<T: Normalize>(mut data: T) -> T {
+    data.normalize();
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_escaped_tags() {
+        assert_eq!(normalize("*[k]ˤ<r>uŋ-s"), "*[k]ˤ<r>uŋ-s");
+        assert_eq!(
+            html_text("<span class=\"IPAchar\">*[k]ˤ&lt;r&gt;uŋ-s</span>"),
+            "*[k]ˤ<r>uŋ-s"
+        );
+    }
+
+    fn owned(pairs: &[(&str, char)]) -> Vec<(String, char)> {
+        pairs.iter().map(|(s, c)| (s.to_string(), *c)).collect()
+    }
+
+    #[test]
+    fn canonicalizes_radicals_and_compatibility_ideographs() {
+        assert_eq!(canonical_chars("⼦"), owned(&[("⼦", '子')]));
+        assert_eq!(canonical_chars("⻌"), owned(&[("⻌", '⻌')]));
+        assert_eq!(canonical_chars("\u{FA5B}"), owned(&[("\u{FA5B}", '者')]));
+        assert_eq!(canonical_chars("\u{2F800}"), owned(&[("\u{2F800}", '丽')]));
+        assert_eq!(canonical_chars("字a"), owned(&[("字", '字'), ("a", 'a')]));
+    }
+
+    #[test]
+    fn keeps_variation_selectors_with_their_character() {
+        assert_eq!(
+            canonical_chars("葛\u{E0100}字\u{FE00}"),
+            owned(&[("葛\u{E0100}", '葛'), ("字\u{FE00}", '字')])
+        );
+        assert_eq!(
+            canonical_chars("\u{FE00}字"),
+            owned(&[("\u{FE00}", '\u{FE00}'), ("字", '字')])
+        );
+    }
+}
@@ -1,28 +1,28 @@
-// FIXME: Filter this
-use crate::{regexes, utils::fetch_row, utils::regex_isolate_one};
+use scraper::Html;
 
-#[derive(Debug, Default)]
-pub struct Data<'a> {
-    pub reading: &'a str,
-    pub old_chinese: &'a str,
+use crate::{
+    error::{Error, Result},
+    table::{sel, text, Table},
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct Data {
+    pub reading: String,
+    pub old_chinese: String,
 }
 
-pub fn fetch(section: &'_ str) -> Vec<Data<'_>> {
-    let re_start = &regexes().old_bs_section_start;
-    let re_end = &regexes().old_bs_section_end;
-    let old_section = if let Some(s) = regex_isolate_one(section, re_start, re_end) {
-        s
-    } else {
-        return vec![];
+pub fn fetch(section: &str) -> Result<Vec<Data>> {
+    let doc = Html::parse_fragment(section);
+    let Some(table) = Table::find(&doc, "Baxter–Sagart", "Sagart") else {
+        return Ok(vec![]);
     };
 
     let mut datas = vec![];
 
-    let readings = fetch_row(
-        old_section,
-        &regexes().mc_reading_start,
-        &regexes().mc_simple_row,
-    );
+    let readings = table.row_text("Reading #");
+    if readings.is_empty() {
+        return Err(Error::malformed(table.name(), "Reading #", ""));
+    }
     for reading in readings {
         datas.push(Data {
             reading,
@@ -30,14 +30,49 @@ pub fn fetch(section: &'_ str) -> Vec<Data<'_>> {
         });
     }
 
-    let old_chineses = fetch_row(
-        old_section,
-        &regexes().old_old_chinese_start,
-        &regexes().old_old_chinese,
-    );
-    for (data, old_chinese) in datas.iter_mut().zip(old_chineses.into_iter()) {
-        data.old_chinese = old_chinese;
+    let old_chineses = table.row_exact("Old Chinese", datas.len())?;
+    for (data, old_chinese) in datas.iter_mut().zip(old_chineses) {
+        data.old_chinese = old_chinese
+            .select(&sel("span.IPAchar"))
+            .next()
+            .map(text)
+            .unwrap_or_else(|| text(old_chinese))
+            .trim_matches('/')
+            .to_string();
     }
 
-    datas
+    Ok(datas)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{normalize::normalized, utils::fixture_pronunciations};
+
+    #[test]
+    fn reads_every_reading() {
+        let datas = fetch(&fixture_pronunciations('字')[0]).expect("malformed");
+        assert_eq!(datas.len(), 1);
+        assert_eq!(datas[0].reading, "1/1");
+        assert_eq!(datas[0].old_chinese, "*[dz]ə-s");
+
+        let datas = fetch(&fixture_pronunciations('行')[0]).expect("malformed");
+        let readings = datas
+            .iter()
+            .map(|d| (d.reading.as_str(), d.old_chinese.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(readings, [("1/2", "*[g]ˤraŋ"), ("2/2", "*[g]ˤraŋ-s")]);
+    }
+
+    #[test]
+    fn keeps_infixes() {
+        let section = concat!(
+            "<table><tr><th>Baxter–Sagart system 1.1 (2014)</th></tr>",
+            "<tr><th>Reading #</th><td>1/1</td></tr>",
+            "<tr><th>Old Chinese</th>",
+            "<td><span class=\"IPAchar\">/*[k]ˤ&lt;r&gt;uŋ-s/</span></td></tr></table>",
+        );
+        let datas = normalized(fetch(section).expect("malformed"));
+        assert_eq!(datas[0].old_chinese, "*[k]ˤ<r>uŋ-s");
+    }
 }
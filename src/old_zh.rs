@@ -1,27 +1,28 @@
-use crate::{regexes, utils::fetch_row, utils::regex_isolate_one};
+use scraper::Html;
 
-#[derive(Debug, Default)]
-pub struct Data<'a> {
-    pub reading: &'a str,
-    pub old_chinese: &'a str,
+use crate::{
+    error::{Error, Result},
+    table::{sel, text, Table},
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct Data {
+    pub reading: String,
+    pub old_chinese: String,
 }
 
-pub fn fetch(section: &'_ str) -> Vec<Data<'_>> {
-    let re_start = &regexes().old_zh_section_start;
-    let re_end = &regexes().old_zh_section_end;
-    let old_section = if let Some(s) = regex_isolate_one(section, re_start, re_end) {
-        s
-    } else {
-        return vec![];
+pub fn fetch(section: &str) -> Result<Vec<Data>> {
+    let doc = Html::parse_fragment(section);
+    let Some(table) = Table::find(&doc, "Zhengzhang", "Zhengzhang") else {
+        return Ok(vec![]);
     };
 
     let mut datas = vec![];
 
-    let readings = fetch_row(
-        old_section,
-        &regexes().mc_reading_start,
-        &regexes().mc_simple_row,
-    );
+    let readings = table.row_text("Reading #");
+    if readings.is_empty() {
+        return Err(Error::malformed(table.name(), "Reading #", ""));
+    }
     for reading in readings {
         datas.push(Data {
             reading,
@@ -29,21 +30,47 @@ pub fn fetch(section: &'_ str) -> Vec<Data<'_>> {
         });
     }
 
-    let old_chineses = fetch_row(
-        old_section,
-        &regexes().old_old_chinese_start,
-        &regexes().old_old_chinese,
-    );
-    for (data, old_chinese) in datas.iter_mut().zip(old_chineses.into_iter()) {
-        let re = &regexes().old_zh_filter;
-        if let Some(caps) = re.captures(old_chinese) {
-            if let Some(oc) = caps.get(1) {
-                data.old_chinese = oc.as_str();
-            }
-        } else {
-            data.old_chinese = old_chinese;
+    let old_chineses = table.row_exact("Old Chinese", datas.len())?;
+    for (data, old_chinese) in datas.iter_mut().zip(old_chineses) {
+        // NOTE: Some readings are followed by a note inside the same span. Only the span's own text
+        // is the reading.
+        data.old_chinese = match old_chinese.select(&sel("span.IPAchar")).next() {
+            Some(span) => span
+                .children()
+                .filter_map(|n| n.value().as_text())
+                .map(|t| &**t)
+                .collect(),
+            None => text(old_chinese),
         }
+        .trim()
+        .trim_matches('/')
+        .to_string();
     }
 
-    datas
+    Ok(datas)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fixture_pronunciations;
+
+    #[test]
+    fn reads_every_reading() {
+        let datas = fetch(&fixture_pronunciations('字')[0]).expect("malformed");
+        assert_eq!(datas.len(), 1);
+        assert_eq!(datas[0].old_chinese, "*zlɯs");
+
+        let pronunciations = fixture_pronunciations('行');
+        let datas = fetch(&pronunciations[0]).expect("malformed");
+        let readings = datas
+            .iter()
+            .map(|d| (d.reading.as_str(), d.old_chinese.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(readings, [("1/2", "*ɡraːŋ"), ("2/2", "*ɡraːŋs")]);
+
+        let datas = fetch(&pronunciations[1]).expect("malformed");
+        assert_eq!(datas.len(), 1);
+        assert_eq!(datas[0].old_chinese, "*ɡaːŋ");
+    }
 }
@@ -1,12 +1,31 @@
-use std::collections::HashSet;
+use clap::ValueEnum;
+use std::{
+    collections::HashSet,
+    io::{self, Write},
+};
 
 use crate::{
-    middle, modern, old_bs, old_zh,
-    request::request,
-    utils::{get_trad, isolate_chinese_section, pronunciation_sections},
-    Args, Variants,
+    definitions::Sense,
+    error::{Diagnostics, Error, Result},
+    etymology,
+    fetch::{fetch_pages, Entry, Lookup, Target},
+    japanese, korean,
+    source::Page,
+    translingual, vietnamese, Args, Variants,
 };
 
+/// What `--baxter` does with a simplified character that stands for several traditional ones.
+#[derive(Debug, Copy, Clone, Default, ValueEnum, PartialEq, Eq)]
+pub enum Pick {
+    /// Show every traditional form, separated by `/`
+    #[default]
+    All,
+    /// Only show the first traditional form
+    First,
+    /// Ask which traditional form to show
+    Prompt,
+}
+
 macro_rules! print_modern {
     ($args: expr, $enum: expr, $prereq: expr, $val: expr, $text: literal) => {
         if $args.modern.contains(&$enum) || ($prereq) {
@@ -17,33 +36,122 @@ macro_rules! print_modern {
     };
 }
 
-pub fn display(args: &Args) -> reqwest::Result<()> {
-    let mut pages = vec![];
-    for c in args.chars.chars() {
-        let page = request(c, args.quiet)?;
-        if let Some(trad) = get_trad(&page) {
-            pages.push((trad, request(trad, args.quiet)?));
-        } else {
-            pages.push((c, page));
+/// One block of the output of [`display`].
+enum Block {
+    /// Characters that are not looked up, printed as they were typed.
+    Text(String),
+    /// A character of the input with one of the forms it was looked up as.
+    Entry(String, Target),
+    /// A character of the input that has nothing to show, with the reason.
+    Empty(String, &'static str),
+}
+
+pub fn display(args: &Args) -> Result<()> {
+    let mut diags = Diagnostics::new(args.strict);
+    let pages = fetch_pages(args, &mut diags)?;
+    let mut blocks = vec![];
+    for Entry { input, lookup, .. } in pages {
+        match lookup {
+            Lookup::Found(targets) => {
+                for target in targets {
+                    blocks.push(Block::Entry(input.clone(), target));
+                }
+            }
+            Lookup::NoChinese => blocks.push(Block::Empty(input, "No Chinese entry")),
+            Lookup::Missing => blocks.push(Block::Empty(input, "No entry found")),
+            Lookup::Failed => blocks.push(Block::Empty(input, "Lookup failed")),
+            // NOTE: A run of such characters is kept together, e.g. a word in another script.
+            Lookup::NotHan => match blocks.last_mut() {
+                Some(Block::Text(text)) => text.push_str(&input),
+                _ => blocks.push(Block::Text(input)),
+            },
         }
     }
+    blocks.retain(|block| !matches!(block, Block::Text(text) if text.trim().is_empty()));
 
-    for (i, (c, page)) in pages.into_iter().enumerate() {
-        let section = isolate_chinese_section(&page);
-
+    for (i, block) in blocks.into_iter().enumerate() {
         if i > 0 {
             println!();
         }
 
-        println!("\x1b[31;1mCharacter: {c}\x1b[0m");
+        let (input, target) = match block {
+            Block::Text(text) => {
+                println!("{}", text.trim());
+                continue;
+            }
+            Block::Empty(input, reason) => {
+                println!("\x1b[31;1mCharacter: {}\x1b[0m", input);
+                println!("{}", reason);
+                continue;
+            }
+            Block::Entry(input, target) => (input, target),
+        };
+        let Target {
+            c,
+            sense,
+            chain,
+            page,
+        } = target;
+
+        // NOTE: The character is shown as it was typed, with what it was looked up as.
+        let mut resolved = vec![];
+        if c.to_string() != input {
+            resolved.push(c.to_string());
+        }
+        resolved.extend(sense);
+        if resolved.is_empty() {
+            println!("\x1b[31;1mCharacter: {}\x1b[0m", input);
+        } else {
+            println!(
+                "\x1b[31;1mCharacter: {} ({})\x1b[0m",
+                input,
+                resolved.join(", ")
+            );
+        }
+        for (j, (from, relation)) in chain.iter().enumerate() {
+            let to = chain.get(j + 1).map_or(c, |(next, _)| *next);
+            println!("{} ({} of {})", from, relation, to);
+        }
+
+        if args.info {
+            print_info(c, &page.translingual());
+        }
+
+        if args.etymology {
+            let etymologies = diags.check(c, page.etymologies())?.unwrap_or_default();
+            for etymology in etymologies.iter().filter(|e| !e.is_empty()) {
+                print_etymology(etymology);
+            }
+        }
 
-        let pronunciations = pronunciation_sections(section);
+        let pronunciations = diags.check(c, page.pronunciations())?.unwrap_or_default();
 
         for (i, pronunciation) in pronunciations.iter().enumerate() {
-            println!("\x1b[32;1mPronunciation {}:\x1b[0m", i + 1);
+            let number = format!("Pronunciation {}", i + 1);
+            // NOTE: The label only says something when the page nests readings in etymologies.
+            if pronunciation.label == "Pronunciation" || pronunciation.label == number {
+                println!("\x1b[32;1m{}:\x1b[0m", number);
+            } else {
+                println!("\x1b[32;1m{} ({}):\x1b[0m", number, pronunciation.label);
+            }
+            let context = format!("{} ({})", c, number);
+
+            if args.definitions {
+                for definitions in &pronunciation.definitions {
+                    println!("\t\x1b[33;1m{}:\x1b[0m", definitions.pos);
+                    print_senses(&definitions.senses, 2);
+                }
+            } else if !pronunciation.definitions.is_empty() {
+                println!("\t\x1b[33;1mMeanings:\x1b[0m");
+                for gloss in pronunciation.glosses() {
+                    println!("\t\t{}", gloss);
+                }
+            }
 
             if args.middle {
-                let data = middle::fetch(pronunciation);
+                let data = diags
+                    .check(&context, pronunciation.middle())?
+                    .unwrap_or_default();
                 for r in data {
                     let openness = if r.open { "開" } else { "合" };
                     let division = match r.division {
@@ -55,19 +163,41 @@ pub fn display(args: &Args) -> reqwest::Result<()> {
                     };
 
                     println!("\t\x1b[33;1mMiddle Chinese (Reading {}):\x1b[0m", r.reading);
-                    println!(
-                        "\t\tRime: {}{}{}{}{}",
-                        r.init, r.fin, r.tone, openness, division
-                    );
-                    println!("\t\tFanqie: {}", r.fanqie);
-                    println!("\t\tBaxter: \x1b[34;1m{}\x1b[0m", r.baxter);
-                    println!("\t\tExpected Mandarin Reflex: {}", r.expected_mandarin);
-                    println!("\t\tExpected Cantonese Reflex: {}", r.expected_cantonese);
+                    // NOTE: Structured sources may only have some fields. What they lack is
+                    // reported rather than left out without a word.
+                    let missing = r.missing();
+                    if !missing.is_empty() {
+                        let partial = Error::Partial {
+                            what: format!("Middle Chinese reading {}", r.reading),
+                            missing,
+                        };
+                        diags.check(&context, Err::<(), _>(partial))?;
+                    }
+                    if !r.init.is_empty() {
+                        println!(
+                            "\t\tRime: {}{}{}{}{}",
+                            r.init, r.fin, r.tone, openness, division
+                        );
+                    }
+                    if !r.fanqie.is_empty() {
+                        println!("\t\tFanqie: {}", r.fanqie);
+                    }
+                    if !r.baxter.is_empty() {
+                        println!("\t\tBaxter: \x1b[34;1m{}\x1b[0m", r.baxter);
+                    }
+                    if !r.expected_mandarin.is_empty() {
+                        println!("\t\tExpected Mandarin Reflex: {}", r.expected_mandarin);
+                    }
+                    if !r.expected_cantonese.is_empty() {
+                        println!("\t\tExpected Cantonese Reflex: {}", r.expected_cantonese);
+                    }
                 }
             }
 
             if args.old {
-                let data_bs = old_bs::fetch(pronunciation);
+                let data_bs = diags
+                    .check(&context, pronunciation.old_bs())?
+                    .unwrap_or_default();
                 if !data_bs.is_empty() {
                     println!("\t\x1b[33;1mOld Chinese (Baxter-Sagart):\x1b[0m");
                     for r in data_bs {
@@ -78,7 +208,9 @@ pub fn display(args: &Args) -> reqwest::Result<()> {
                     }
                 }
 
-                let data_zh = old_zh::fetch(pronunciation);
+                let data_zh = diags
+                    .check(&context, pronunciation.old_zh())?
+                    .unwrap_or_default();
                 if !data_zh.is_empty() {
                     println!("\t\x1b[33;1mOld Chinese (Zhengzhang):\x1b[0m");
                     for r in data_zh {
@@ -91,7 +223,9 @@ pub fn display(args: &Args) -> reqwest::Result<()> {
             }
 
             if !args.modern.is_empty() {
-                let data = modern::fetch(pronunciation);
+                let data = diags
+                    .check(&context, pronunciation.modern())?
+                    .unwrap_or_default();
                 let all = args.modern.contains(&Variants::All);
                 let man = all || args.modern.contains(&Variants::Man);
                 let can = all || args.modern.contains(&Variants::Can);
@@ -290,58 +424,274 @@ pub fn display(args: &Args) -> reqwest::Result<()> {
                 );
             }
         }
+
+        // NOTE: Readings of other languages are for the whole page, so they are shown once, after
+        // the Middle Chinese readings of every pronunciation.
+        if args.japanese {
+            print_japanese(page.japanese().as_ref());
+        }
+        if args.korean {
+            print_korean(page.korean().as_ref());
+        }
+        if args.vietnamese {
+            print_vietnamese(page.vietnamese().as_ref());
+        }
     }
 
-    Ok(())
+    diags.finish()
+}
+
+fn print_info(c: char, data: &translingual::Data) {
+    println!("\x1b[32;1mInfo:\x1b[0m");
+    if let Some(radical) = &data.radical {
+        let number = data
+            .radical_number
+            .as_ref()
+            .map(|n| format!(" ({})", n))
+            .unwrap_or_default();
+        // NOTE: Additional strokes are zero-padded, e.g. `03`.
+        let additional = data
+            .additional_strokes
+            .as_ref()
+            .map(|n| {
+                let n = n.parse::<u32>().map_or(n.clone(), |n| n.to_string());
+                format!(", {} additional strokes", n)
+            })
+            .unwrap_or_default();
+        println!("\tRadical: {}{}{}", radical, number, additional);
+    }
+    if let Some(strokes) = &data.strokes {
+        println!("\tStrokes: {}", strokes);
+    }
+    if let Some(ids) = &data.ids {
+        println!("\tIDS: {}", ids);
+    }
+    if let Some(cangjie) = &data.cangjie {
+        println!("\tCangjie: {}", cangjie);
+    }
+    if let Some(four_corner) = &data.four_corner {
+        println!("\tFour-corner: {}", four_corner);
+    }
+    println!("\tUnicode: {}", translingual::unicode(c));
 }
 
-pub fn baxter(args: &Args) -> reqwest::Result<()> {
-    let mut pages = vec![];
-    for c in args.chars.chars() {
-        let page = request(c, args.quiet)?;
-        if let Some(trad) = get_trad(&page) {
-            pages.push((trad, request(trad, args.quiet)?));
+fn print_etymology(data: &etymology::Data) {
+    println!("\x1b[32;1m{}:\x1b[0m", data.title);
+    if !data.text.is_empty() {
+        println!("\t{}", data.text);
+    }
+    for (name, values) in [
+        ("Semantic", &data.semantic),
+        ("Phonetic", &data.phonetic),
+        ("Proto-Sino-Tibetan", &data.sino_tibetan),
+        ("Old Chinese", &data.old_chinese),
+    ] {
+        if !values.is_empty() {
+            println!("\t\x1b[33;1m{}:\x1b[0m {}", name, values.join(", "));
+        }
+    }
+}
+
+fn print_japanese(data: Option<&japanese::Data>) {
+    println!("\x1b[32;1mJapanese:\x1b[0m");
+    let Some(data) = data else {
+        println!("\tNo Japanese entry");
+        return;
+    };
+    if data.is_empty() {
+        println!("\tNo readings");
+    }
+    for (name, readings) in data.fields() {
+        if readings.is_empty() {
+            continue;
+        }
+        let readings = readings
+            .iter()
+            .map(|r| match &r.historical {
+                Some(historical) => format!("{} ({})", r.kana, historical),
+                None => r.kana.clone(),
+            })
+            .collect::<Vec<_>>();
+        println!("\t{}: \x1b[34;1m{}\x1b[0m", name, readings.join(", "));
+    }
+}
+
+fn print_korean(data: Option<&korean::Data>) {
+    println!("\x1b[32;1mKorean:\x1b[0m");
+    let Some(data) = data else {
+        println!("\tNo Korean entry");
+        return;
+    };
+    if data.is_empty() {
+        println!("\tNo readings");
+    }
+    for r in &data.readings {
+        println!(
+            "\tReading: \x1b[34;1m{}\x1b[0m (Revised: {}, Yale: {})",
+            r.hangul, r.revised, r.yale
+        );
+    }
+    for e in &data.eumhun {
+        println!("\tEumhun: {} {}", e.hun, e.eum);
+    }
+    if !data.middle_korean.is_empty() {
+        println!("\tMiddle Korean: {}", data.middle_korean.join(", "));
+    }
+}
+
+fn print_vietnamese(data: Option<&vietnamese::Data>) {
+    println!("\x1b[32;1mVietnamese:\x1b[0m");
+    let Some(data) = data else {
+        println!("\tNo Vietnamese entry");
+        return;
+    };
+    if data.is_empty() {
+        println!("\tNo readings");
+    }
+    for (name, readings) in [("Hán Việt", &data.han_viet), ("Nôm", &data.nom)] {
+        if readings.is_empty() {
+            continue;
+        }
+        let readings = readings
+            .iter()
+            .map(|r| format!("\x1b[34;1m{}\x1b[0m ({})", r.text, r.tone))
+            .collect::<Vec<_>>();
+        println!("\t{}: {}", name, readings.join(", "));
+    }
+}
+
+/// Prints numbered senses, indented by `depth` tabs, with their sub-senses under them.
+fn print_senses(senses: &[Sense], depth: usize) {
+    let indent = "\t".repeat(depth);
+    for (i, sense) in senses.iter().enumerate() {
+        if sense.labels.is_empty() {
+            println!("{}{}. {}", indent, i + 1, sense.gloss);
         } else {
-            pages.push((c, page));
+            println!(
+                "{}{}. ({}) {}",
+                indent,
+                i + 1,
+                sense.labels.join(", "),
+                sense.gloss
+            );
         }
+        print_senses(&sense.senses, depth + 1);
     }
+}
 
+pub fn baxter(args: &Args) -> Result<()> {
+    let mut diags = Diagnostics::new(args.strict);
+    let pages = fetch_pages(args, &mut diags)?;
+    let pages = pages
+        .into_iter()
+        .map(|Entry { input, c, lookup }| match lookup {
+            Lookup::Found(targets) => (input, Lookup::Found(pick(c, targets, args.pick))),
+            lookup => (input, lookup),
+        })
+        .collect::<Vec<_>>();
+
+    // NOTE: The header keeps the script of the input even if readings come from another form.
     for (c, _) in pages.iter() {
         print!("{}", c);
     }
     println!();
 
-    for (i, (_, page)) in pages.into_iter().enumerate() {
-        let section = isolate_chinese_section(&page);
-
-        if i > 0 {
+    // NOTE: Characters that are not Han stay in place, so readings are only separated from
+    // each other.
+    let mut after_reading = false;
+    for (c, lookup) in pages.into_iter() {
+        if matches!(lookup, Lookup::NotHan) {
+            print!("{}", c);
+            after_reading = false;
+            continue;
+        }
+        if after_reading {
             print!(" ");
         }
-
-        let mut list = vec![];
-
-        let pronunciations = pronunciation_sections(section);
-        for pronunciation in pronunciations {
-            let data = middle::fetch(pronunciation);
-            for r in data {
-                list.push(r.baxter);
+        after_reading = true;
+
+        let targets = match lookup {
+            Lookup::Found(targets) if !targets.is_empty() => targets,
+            // NOTE: `?` tells a lookup that failed apart from a character without readings.
+            Lookup::Failed => {
+                print!("?");
+                continue;
+            }
+            _ => {
+                print!("[]");
+                continue;
             }
+        };
+        let mut readings = vec![];
+        for target in targets {
+            readings.push(baxter_readings(&mut diags, target.c, &target.page)?);
         }
+        print!("{}", readings.join("/"));
+    }
 
-        let mut seen = HashSet::new();
-        let list_uniq = list
-            .into_iter()
-            .filter(|item| seen.insert(*item))
-            .collect::<Vec<_>>();
+    println!();
 
-        if list_uniq.is_empty() {
-            print!("[]");
-        } else {
-            print!("{}", list_uniq.join("|"));
+    diags.finish()
+}
+
+/// The distinct Baxter transcriptions of `page` joined by `|`, or `[]` if there are none.
+fn baxter_readings(diags: &mut Diagnostics, c: char, page: &Page) -> Result<String> {
+    let mut list = vec![];
+
+    let pronunciations = diags.check(c, page.pronunciations())?.unwrap_or_default();
+    for pronunciation in pronunciations {
+        let data = diags.check(c, pronunciation.middle())?.unwrap_or_default();
+        for r in data {
+            list.push(r.baxter);
         }
     }
 
-    println!();
+    let mut seen = HashSet::new();
+    let list_uniq = list
+        .into_iter()
+        .filter(|item| seen.insert(item.clone()))
+        .collect::<Vec<_>>();
+
+    if list_uniq.is_empty() {
+        Ok("[]".to_string())
+    } else {
+        Ok(list_uniq.join("|"))
+    }
+}
+
+/// Narrows the traditional forms of `c` down according to `--pick`.
+fn pick(c: char, mut targets: Vec<Target>, pick: Pick) -> Vec<Target> {
+    if targets.len() < 2 {
+        return targets;
+    }
 
-    Ok(())
+    match pick {
+        Pick::All => targets,
+        Pick::First => {
+            targets.truncate(1);
+            targets
+        }
+        Pick::Prompt => {
+            eprintln!("{} stands for several characters:", c);
+            for (i, target) in targets.iter().enumerate() {
+                match target.sense {
+                    Some(ref sense) => eprintln!("  {}) {} ({})", i + 1, target.c, sense),
+                    None => eprintln!("  {}) {}", i + 1, target.c),
+                }
+            }
+            eprint!("Pick one [1]: ");
+            let _ = io::stderr().flush();
+
+            // NOTE: Anything that is not a valid choice, including EOF, picks the first one.
+            let mut line = String::new();
+            let _ = io::stdin().read_line(&mut line);
+            let choice = line
+                .trim()
+                .parse::<usize>()
+                .ok()
+                .filter(|i| (1..=targets.len()).contains(i))
+                .unwrap_or(1);
+            vec![targets.swap_remove(choice - 1)]
+        }
+    }
 }
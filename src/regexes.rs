@@ -2,86 +2,71 @@ use regex::Regex;
 use std::sync::OnceLock;
 
 pub struct Regexes {
-    pub row_end: Regex,
     pub isolate_chinese_section: Regex,
-    pub pronunciation_sections: Regex,
-    pub traditional_char: Regex,
+    pub html_heading: Regex,
+    pub see_redirect: Regex,
+    pub see_target: Regex,
+    pub see_relation: Regex,
+    pub html_tag: Regex,
+    pub html_entity: Regex,
 
-    pub mc_section_start: Regex,
-    pub mc_section_end: Regex,
-    pub mc_simple_row: Regex,
-    pub mc_reading_start: Regex,
-    pub mc_init_start: Regex,
-    pub mc_fin_start: Regex,
-    pub mc_init_fin: Regex,
-    pub mc_tone_start: Regex,
-    pub mc_tone: Regex,
-    pub mc_open_start: Regex,
-    pub mc_div_start: Regex,
-    pub mc_fanqie_start: Regex,
-    pub mc_fanqie: Regex,
-    pub mc_baxter_start: Regex,
-    pub mc_baxter: Regex,
-    pub mc_mandarin_start: Regex,
-    pub mc_cantonese_start: Regex,
-    pub mc_mandarin: Regex,
-    pub mc_cantonese: Regex,
+    pub modern_data: Regex,
+    pub etymology_semantic: Regex,
+    pub etymology_phonetic: Regex,
+    pub etymology_sino_tibetan: Regex,
+    pub etymology_old_chinese: Regex,
+    pub template_data: Regex,
+    pub middle_korean: Regex,
 
-    pub old_old_chinese_start: Regex,
-    pub old_old_chinese: Regex,
-    pub old_bs_section_start: Regex,
-    pub old_bs_section_end: Regex,
-    pub old_zh_section_start: Regex,
-    pub old_zh_section_end: Regex,
-    pub old_zh_filter: Regex,
+    pub zim_headline: Regex,
+    pub zim_heading: Regex,
+    pub zim_chinese_link: Regex,
 
-    pub modern_data: Regex,
+    pub wikitext_comment: Regex,
+    pub wikitext_chinese_heading: Regex,
+    pub wikitext_language_heading: Regex,
+    pub wikitext_heading: Regex,
+    pub wikitext_tag: Regex,
 }
 
 impl Regexes {
     pub fn new() -> Self {
         Self {
-            row_end: Self::re(r#"</tr>"#),
             isolate_chinese_section: Self::re(r#"<h2 id=".*">(.*)</h2>"#),
-            pronunciation_sections: Self::re(r#"<h[34] id="Pronunciation.*">(.*)</h[34]>"#),
-            traditional_char: Self::re(
-                r#" – see <span class="Hant" lang="zh"><a rel="mw:WikiLink" href="\.\/.#Chinese" title="(.)">"#,
+            html_heading: Self::re(r#"<h([3-6])(?:\s[^>]*)?>(.*?)</h[3-6]>"#),
+            see_redirect: Self::re(r#"(?s) – see (.*?)(?:</p>|</li>|$)"#),
+            see_target: Self::re(r#"(\p{Han})(?: \(“([^”]*)”\))?"#),
+            see_relation: Self::re(
+                r#"(?i)\(?this (?:character|term) is (?:a|an|the) (.*?) form of"#,
             ),
-            mc_section_start: Self::re(r#"title="w:Middle Chinese" class="extiw">Middle Chinese"#),
-            mc_section_end: Self::re(r#"</tbody></table></div></div>"#),
-            mc_simple_row: Self::re(r#"<td.*>(.*)</td>"#),
-            mc_reading_start: Self::re(r#"<th.*><small>Reading #</small></th>\n"#),
-            mc_init_start: Self::re(r#"<th.*><small>Initial</small>"#),
-            mc_fin_start: Self::re(r#"<th.*><small>Final</small>"#),
-            mc_init_fin: Self::re(r#"title="Appendix:Middle Chinese">(.*)</a></span>"#),
-            mc_tone_start: Self::re(r#"<th.*><small>Tone</small>"#),
-            mc_tone: Self::re(r#"<td.*>(.*) .*</td>"#),
-            mc_open_start: Self::re(r#"<th.*><small>Openness</small>"#),
-            mc_div_start: Self::re(r#"<th.*><small>Division</small>"#),
-            mc_fanqie_start: Self::re(r#"title="w:Fanqie" class="extiw">Fanqie"#),
-            mc_fanqie: Self::re(r#"title=".*">(.*)</a><.*title=".*">(.*)</a>"#),
-            mc_baxter_start: Self::re(
-                r#"title="w:Baxter's transcription for Middle Chinese" class="extiw">Baxter"#,
+            html_tag: Self::re(r#"<[^>]*>"#),
+            html_entity: Self::re(r#"&(#[0-9]+|#[xX][0-9a-fA-F]+|[a-zA-Z]+);"#),
+            modern_data: Self::re(r#"<div class="standard-box zhpron" .* data-mw='(.*)'"#),
+            etymology_semantic: Self::re(r#"\bsemantic (\p{Han})"#),
+            etymology_phonetic: Self::re(r#"\bphonetic (\p{Han})"#),
+            etymology_sino_tibetan: Self::re(
+                r#"Proto-Sino-Tibetan (\*[^\s,;()“”]+(?:, \*[^\s,;()“”]+)*)"#,
             ),
-            mc_baxter: Self::re(r#"<span lang="zh">(.*)</span>"#),
-            mc_mandarin_start: Self::re(r#"<small>Expected<br/>Mandarin<br/>Reflex</small>"#),
-            mc_cantonese_start: Self::re(r#"<small>Expected<br/>Cantonese<br/>Reflex</small>"#),
-            mc_mandarin: Self::re(r#"<td.*>(.*)</td>"#),
-            mc_cantonese: Self::re(r#"<td.*>(.*<sup>.</sup>)</td>"#),
-            old_old_chinese_start: Self::re(r#"<small>Old<br/>Chinese</small>"#),
-            old_old_chinese: Self::re(r#"<span class="IPAchar">/(.*)/.*</span>"#),
-            old_bs_section_start: Self::re(
-                r#"title="w:William H. Baxter" class="extiw">Baxter</a>"#,
+            etymology_old_chinese: Self::re(
+                r#"\b(?:OC|Old Chinese) (\*[^\s,;()“”]+(?:, \*[^\s,;()“”]+)*)"#,
             ),
-            old_bs_section_end: Self::re(
-                r#"</tbody></table>\n<table class="wikitable mw-collapsible mw-collapsed".*>"#,
+            template_data: Self::re(r#"data-mw='([^']*)'"#),
+            middle_korean: Self::re(
+                r#"Middle Korean(?: reading)?:?\s*([\p{Hangul}\x{302E}\x{302F}]+)"#,
             ),
-            old_zh_section_start: Self::re(
-                r#"title="w:Zhengzhang Shangfang" class="extiw">Zhengzhang</a> system \(2003\)"#,
+            zim_headline: Self::re(
+                r#"<h([2-6])[^>]*>\s*<span class="mw-headline" id="([^"]*)"[^>]*>(.*?)</span>.*?</h[2-6]>"#,
+            ),
+            zim_heading: Self::re(r#"<h([2-6])\s[^>]*\bid="([^"]*)"[^>]*>"#),
+            zim_chinese_link: Self::re(r#"<a href="[^"]*#Chinese" title="([^"]*)">"#),
+            wikitext_comment: Self::re(r#"(?s)<!--.*?-->"#),
+            wikitext_chinese_heading: Self::re(r#"(?m)^==\s*Chinese\s*==[ \t]*$"#),
+            wikitext_language_heading: Self::re(r#"(?m)^==[^=].*==[ \t]*$"#),
+            wikitext_heading: Self::re(r#"(?m)^(={3,6})\s*([^=].*?)\s*(={3,6})[ \t]*$"#),
+            // NOTE: Only tags wikitext allows, since readings like `*[k]ˤ<r>uŋ-s` look like one.
+            wikitext_tag: Self::re(
+                r#"(?i)</?(?:abbr|b|big|br|code|del|div|i|ins|nowiki|p|ref|references|s|small|span|strike|sub|sup|u)\b[^>]*>"#,
             ),
-            old_zh_section_end: Self::re(r#"</tbody></table></div></div></div></div><link"#),
-            old_zh_filter: Self::re(r#"(\*.*)<.*>.*</.*>"#),
-            modern_data: Self::re(r#"<div class="standard-box zhpron" .* data-mw='(.*)'"#),
         }
     }
 
@@ -1,8 +1,24 @@
-use reqwest::header::USER_AGENT;
+use reqwest::{
+    blocking::Response as HttpResponse,
+    header::{HeaderMap, ETAG, RETRY_AFTER},
+    StatusCode,
+};
+use serde_json::{json, Value};
 use std::{
-    sync::OnceLock,
+    collections::hash_map::RandomState,
+    fs,
+    hash::{BuildHasher, Hasher},
+    io,
+    path::{Path, PathBuf},
+    sync::Mutex,
     thread::sleep,
-    time::{Duration, SystemTime},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    error::{Error, Result},
+    wikitext::Module,
+    Args,
 };
 
 const REQUEST_USER_AGENT: &str = concat!(
@@ -11,40 +27,485 @@ const REQUEST_USER_AGENT: &str = concat!(
     env!("CARGO_PKG_VERSION"),
     " (https://github.com/spitulax/wangyun; bintangadiputra@proton.me)",
 );
-const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
-// TODO: Should we async?
-// NOTE: Since we use blocking, 100ms gap per request should be fine to cap the requests to 10 per
-// second as for Wikimedia's requirement. This results in slower requests however, since when we
-// request 10 times or fewer it should also met the requirement even without this delay.
-const REQUEST_PER_SECOND: u64 = 10;
-const REQUEST_GAP: Duration = Duration::from_millis(1000 / REQUEST_PER_SECOND);
-static LAST_REQUEST_TIME: OnceLock<SystemTime> = OnceLock::new();
-
-pub fn request(c: char, quiet: bool) -> reqwest::Result<String> {
-    if let Some(t) = LAST_REQUEST_TIME.get() {
-        let dur = SystemTime::now()
-            .duration_since(*t)
-            .expect("incorrect time");
-        if dur < REQUEST_GAP {
-            sleep(REQUEST_GAP - dur);
-        }
-    }
-
-    let client = reqwest::blocking::Client::new();
-    if !quiet {
-        eprintln!("Requesting {}...", c);
-    }
-    let response = client
-        .get(format!(
-            "https://en.wiktionary.org/api/rest_v1/page/html/{}",
-            c
-        ))
-        .header(USER_AGENT, REQUEST_USER_AGENT)
-        .timeout(REQUEST_TIMEOUT)
-        .send()?
-        .text();
-
-    LAST_REQUEST_TIME.get_or_init(SystemTime::now);
-
-    response
+pub const DEFAULT_ENDPOINT: &str = "https://en.wiktionary.org/api/rest_v1/page/html";
+pub const DEFAULT_WIKITEXT_ENDPOINT: &str =
+    "https://en.wiktionary.org/w/index.php?action=raw&title=";
+pub const DEFAULT_TIMEOUT: u64 = 10;
+pub const DEFAULT_RETRIES: u32 = 3;
+const BACKOFF_BASE: Duration = Duration::from_millis(500);
+const BACKOFF_MAX: Duration = Duration::from_secs(30);
+// NOTE: Wikimedia asks us to stay under 10 requests per second. The bucket only holds one token,
+// so even after a pause no one-second window has more than that.
+const REQUEST_PER_SECOND: f64 = 10.0;
+const BUCKET_CAPACITY: f64 = 1.0;
+
+pub struct Response {
+    pub body: String,
+    /// Revision ID of the page, or 0 if the server did not report one.
+    pub revision: u64,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Fixtures {
+    None,
+    /// Write every response to the directory.
+    Record(PathBuf),
+    /// Serve responses from the directory instead of the network.
+    Replay(PathBuf),
+}
+
+/// HTTP client shared by every worker. All requests go through the same token bucket.
+pub struct Client {
+    inner: reqwest::blocking::Client,
+    bucket: TokenBucket,
+    endpoint: String,
+    wikitext_endpoint: String,
+    fixtures: Fixtures,
+    retries: u32,
+    quiet: bool,
+    /// Characters that needed at least one retry.
+    retried: Mutex<Vec<char>>,
+}
+
+impl Client {
+    pub fn new(args: &Args) -> Self {
+        Self {
+            inner: reqwest::blocking::Client::builder()
+                .user_agent(REQUEST_USER_AGENT)
+                .timeout(Duration::from_secs(args.timeout))
+                .gzip(true)
+                .brotli(true)
+                .build()
+                .expect("failed to build HTTP client"),
+            bucket: TokenBucket::new(BUCKET_CAPACITY, REQUEST_PER_SECOND),
+            endpoint: args.endpoint.trim_end_matches('/').to_string(),
+            wikitext_endpoint: args.wikitext_endpoint.clone(),
+            fixtures: if let Some(ref dir) = args.record {
+                Fixtures::Record(dir.clone())
+            } else if let Some(ref dir) = args.replay {
+                Fixtures::Replay(dir.clone())
+            } else {
+                Fixtures::None
+            },
+            retries: args.retries,
+            quiet: args.quiet,
+            retried: Mutex::new(vec![]),
+        }
+    }
+
+    pub fn fixtures(&self) -> &Fixtures {
+        &self.fixtures
+    }
+
+    /// Requests the Parsoid HTML of `c`.
+    /// Returns `None` when there is no page for `c`, or when replaying and the fixture of `c`
+    /// does not exist.
+    pub fn request(&self, c: char) -> Result<Option<Response>> {
+        self.request_url(c, format!("{}/{}", self.endpoint, c), "html")
+    }
+
+    /// Requests the raw wikitext of `c`.
+    pub fn request_wikitext(&self, c: char) -> Result<Option<Response>> {
+        self.request_url(c, format!("{}{}", self.wikitext_endpoint, c), "wikitext")
+    }
+
+    /// Requests the raw wikitext of a data module of `c`.
+    pub fn request_module(&self, c: char, module: Module) -> Result<Option<Response>> {
+        let url = format!("{}{}{}", self.wikitext_endpoint, module.prefix(), c);
+        self.request_url(c, url, module.extension())
+    }
+
+    fn request_url(&self, c: char, url: String, kind: &str) -> Result<Option<Response>> {
+        if let Fixtures::Replay(ref dir) = self.fixtures {
+            return Ok(read_fixture(dir, c, kind));
+        }
+
+        let mut attempt = 0;
+        let response = loop {
+            self.bucket.acquire();
+
+            if !self.quiet {
+                eprintln!("Requesting {}...", c);
+            }
+            let result = self.inner.get(&url).send();
+            let wait = match result {
+                Ok(response) if is_retryable(&response) => {
+                    if attempt >= self.retries {
+                        // NOTE: `maxlag` may come with a 200, which `error_for_status` lets
+                        // through.
+                        response.error_for_status()?;
+                        return Err(Error::Lagged);
+                    }
+                    retry_after(response.headers()).unwrap_or_else(|| backoff(attempt))
+                }
+                Ok(response) if response.status() == StatusCode::NOT_FOUND => return Ok(None),
+                // NOTE: The body of any other error would be read as a page without a Chinese
+                // section, so it fails the character instead.
+                Ok(response) => break response.error_for_status()?,
+                Err(e) if e.is_timeout() || e.is_connect() => {
+                    if attempt >= self.retries {
+                        return Err(e.into());
+                    }
+                    backoff(attempt)
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+            if attempt == 0 {
+                self.retried.lock().expect("poisoned lock").push(c);
+            }
+            if !self.quiet {
+                eprintln!("Retrying {} in {:.1}s...", c, wait.as_secs_f64());
+            }
+            sleep(wait);
+            attempt += 1;
+        };
+
+        let revision = response
+            .headers()
+            .get(ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .and_then(parse_revision)
+            .unwrap_or(0);
+
+        let bytes = response.bytes()?;
+
+        if let Fixtures::Record(ref dir) = self.fixtures {
+            if let Err(e) = write_fixture(dir, c, kind, &bytes, revision) {
+                eprintln!("Failed to record {}: {}", c, e);
+            }
+        }
+
+        Ok(Some(Response {
+            body: String::from_utf8_lossy(&bytes).into_owned(),
+            revision,
+        }))
+    }
+
+    pub fn retried(&self) -> Vec<char> {
+        self.retried.lock().expect("poisoned lock").clone()
+    }
+}
+
+/// Writes a page in the layout read by [`read_fixture`]. `kind` is the extension of the body.
+pub fn write_fixture(
+    dir: &Path,
+    c: char,
+    kind: &str,
+    body: &[u8],
+    revision: u64,
+) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    fs::write(dir.join(format!("{:x}.{}", c as u32, kind)), body)?;
+    let meta = json!({
+        "char": c.to_string(),
+        "revision": revision,
+    });
+    fs::write(dir.join(format!("{:x}.json", c as u32)), meta.to_string())
+}
+
+pub fn read_fixture(dir: &Path, c: char, kind: &str) -> Option<Response> {
+    let body = fs::read(dir.join(format!("{:x}.{}", c as u32, kind))).ok()?;
+    let revision = fs::read_to_string(dir.join(format!("{:x}.json", c as u32)))
+        .ok()
+        .and_then(|meta| serde_json::from_str::<Value>(&meta).ok())
+        .and_then(|meta| meta.get("revision")?.as_u64())
+        .unwrap_or(0);
+    Some(Response {
+        body: String::from_utf8_lossy(&body).into_owned(),
+        revision,
+    })
+}
+
+fn is_retryable(response: &HttpResponse) -> bool {
+    // NOTE: Wikimedia reports replication lag as a `maxlag` API error, sometimes with a 200.
+    let maxlag = response
+        .headers()
+        .get("mediawiki-api-error")
+        .is_some_and(|v| v == "maxlag");
+    maxlag
+        || matches!(
+            response.status(),
+            StatusCode::TOO_MANY_REQUESTS
+                | StatusCode::INTERNAL_SERVER_ERROR
+                | StatusCode::BAD_GATEWAY
+                | StatusCode::SERVICE_UNAVAILABLE
+                | StatusCode::GATEWAY_TIMEOUT
+        )
+}
+
+/// Only the delay-seconds form of `Retry-After` is supported.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let secs = headers
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    Some(Duration::from_secs(secs).min(BACKOFF_MAX))
+}
+
+/// Exponential backoff with jitter between half and the full delay.
+fn backoff(attempt: u32) -> Duration {
+    let delay = BACKOFF_BASE
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(BACKOFF_MAX);
+    let random = RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64;
+    delay.mul_f64(0.5 + random / 2.0)
+}
+
+struct TokenBucket {
+    capacity: f64,
+    /// Tokens added per second.
+    rate: f64,
+    state: Mutex<(f64, Instant)>,
+}
+
+impl TokenBucket {
+    fn new(capacity: f64, rate: f64) -> Self {
+        Self {
+            capacity,
+            rate,
+            state: Mutex::new((capacity, Instant::now())),
+        }
+    }
+
+    /// Blocks until a token is available and takes it.
+    fn acquire(&self) {
+        while let Some(wait) = self.try_take(Instant::now()) {
+            sleep(wait);
+        }
+    }
+
+    /// Takes a token if one is available at `now`. Otherwise returns how long until there is one.
+    fn try_take(&self, now: Instant) -> Option<Duration> {
+        let mut state = self.state.lock().expect("poisoned lock");
+        let (ref mut tokens, ref mut last) = *state;
+        *tokens = (*tokens + now.saturating_duration_since(*last).as_secs_f64() * self.rate)
+            .min(self.capacity);
+        *last = now.max(*last);
+        if *tokens >= 1.0 {
+            *tokens -= 1.0;
+            return None;
+        }
+        Some(Duration::from_secs_f64((1.0 - *tokens) / self.rate))
+    }
+}
+
+/// Parses the revision ID from an ETag such as `W/"81273512/e5c1f2e0-..."`.
+fn parse_revision(etag: &str) -> Option<u64> {
+    etag.trim_start_matches("W/")
+        .trim_matches('"')
+        .split('/')
+        .next()?
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        thread::{self, JoinHandle},
+    };
+
+    /// Answers each connection with the next of `responses`, then returns how many it answered.
+    /// `None` accepts the connection but never answers it.
+    fn serve(responses: Vec<Option<String>>) -> (String, JoinHandle<usize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind");
+        let url = format!("http://{}", listener.local_addr().expect("no address"));
+        let handle = thread::spawn(move || {
+            let mut served = 0;
+            for response in responses {
+                let (mut stream, _) = listener.accept().expect("failed to accept");
+                let mut reader = BufReader::new(stream.try_clone().expect("failed to clone"));
+                let mut line = String::new();
+                while reader.read_line(&mut line).is_ok_and(|n| n > 2) {
+                    line.clear();
+                }
+                served += 1;
+                match response {
+                    Some(response) => {
+                        let _ = stream.write_all(response.as_bytes());
+                    }
+                    None => {
+                        thread::spawn(move || {
+                            sleep(Duration::from_secs(2));
+                            drop(stream);
+                        });
+                    }
+                }
+            }
+            served
+        });
+        (url, handle)
+    }
+
+    fn response(status: &str, headers: &[&str], body: &str) -> Option<String> {
+        let mut response = format!("HTTP/1.1 {}\r\n", status);
+        for header in headers {
+            response.push_str(&format!("{}\r\n", header));
+        }
+        response.push_str(&format!(
+            "Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        ));
+        Some(response)
+    }
+
+    fn client(url: &str, retries: u32) -> Client {
+        Client::new(&Args::parse_from([
+            "wangyun",
+            "字",
+            "--quiet",
+            "--endpoint",
+            url,
+            "--retries",
+            &retries.to_string(),
+            "--timeout",
+            "1",
+        ]))
+    }
+
+    #[test]
+    fn retries_until_success() {
+        let (url, server) = serve(vec![
+            response("429 Too Many Requests", &[], ""),
+            response("503 Service Unavailable", &[], ""),
+            response("200 OK", &[r#"ETag: W/"123/abc""#], "page"),
+        ]);
+        let client = client(&url, 3);
+        let page = client
+            .request('字')
+            .expect("request failed")
+            .expect("no page");
+        assert_eq!(page.body, "page");
+        assert_eq!(page.revision, 123);
+        assert_eq!(client.retried(), vec!['字']);
+        assert_eq!(server.join().expect("server panicked"), 3);
+    }
+
+    #[test]
+    fn honours_retry_after() {
+        let (url, server) = serve(vec![
+            response("503 Service Unavailable", &["Retry-After: 2"], ""),
+            response("200 OK", &[], "page"),
+        ]);
+        let start = Instant::now();
+        let page = client(&url, 1).request('字').expect("request failed");
+        // NOTE: The first backoff is at most half a second.
+        assert!(start.elapsed() >= Duration::from_secs(2));
+        assert_eq!(page.expect("no page").body, "page");
+        assert_eq!(server.join().expect("server panicked"), 2);
+    }
+
+    #[test]
+    fn retries_on_maxlag() {
+        let maxlag = ["MediaWiki-API-Error: maxlag", "Retry-After: 0"];
+        let (url, server) = serve(vec![
+            response("200 OK", &maxlag, "lagged"),
+            response("200 OK", &[], "page"),
+        ]);
+        let page = client(&url, 1).request('字').expect("request failed");
+        assert_eq!(page.expect("no page").body, "page");
+        assert_eq!(server.join().expect("server panicked"), 2);
+    }
+
+    #[test]
+    fn fails_when_retries_run_out() {
+        let (url, server) = serve(vec![
+            response("429 Too Many Requests", &["Retry-After: 0"], ""),
+            response("429 Too Many Requests", &["Retry-After: 0"], ""),
+        ]);
+        let result = client(&url, 1).request('字');
+        assert!(matches!(
+            result,
+            Err(Error::Network(ref e)) if e.status() == Some(StatusCode::TOO_MANY_REQUESTS)
+        ));
+        assert_eq!(server.join().expect("server panicked"), 2);
+    }
+
+    #[test]
+    fn fails_when_still_lagged() {
+        let maxlag = ["MediaWiki-API-Error: maxlag", "Retry-After: 0"];
+        let (url, server) = serve(vec![
+            response("200 OK", &maxlag, "lagged"),
+            response("200 OK", &maxlag, "lagged"),
+        ]);
+        let result = client(&url, 1).request('字');
+        assert!(matches!(result, Err(Error::Lagged)));
+        assert_eq!(server.join().expect("server panicked"), 2);
+    }
+
+    #[test]
+    fn has_no_page_on_not_found() {
+        let (url, server) = serve(vec![response("404 Not Found", &[], "not found")]);
+        let page = client(&url, 1).request('字').expect("request failed");
+        assert!(page.is_none());
+        assert_eq!(server.join().expect("server panicked"), 1);
+    }
+
+    #[test]
+    fn fails_on_client_error() {
+        let (url, server) = serve(vec![response("403 Forbidden", &[], "forbidden")]);
+        let result = client(&url, 1).request('字');
+        assert!(matches!(
+            result,
+            Err(Error::Network(ref e)) if e.status() == Some(StatusCode::FORBIDDEN)
+        ));
+        assert_eq!(server.join().expect("server panicked"), 1);
+    }
+
+    #[test]
+    fn retries_timeouts() {
+        let (url, server) = serve(vec![None, response("200 OK", &[], "page")]);
+        let page = client(&url, 1).request('字').expect("request failed");
+        assert_eq!(page.expect("no page").body, "page");
+        assert_eq!(server.join().expect("server panicked"), 2);
+    }
+
+    #[test]
+    fn fails_on_timeout() {
+        let (url, server) = serve(vec![None]);
+        let result = client(&url, 0).request('字');
+        assert!(matches!(result, Err(Error::Network(ref e)) if e.is_timeout()));
+        assert_eq!(server.join().expect("server panicked"), 1);
+    }
+
+    #[test]
+    fn bucket_never_exceeds_rate() {
+        let bucket = TokenBucket::new(BUCKET_CAPACITY, REQUEST_PER_SECOND);
+        let start = Instant::now();
+        let mut now = start;
+        let mut taken = vec![];
+
+        // A pause long enough to fill any bucket, then a burst.
+        for pause in [0, 5] {
+            now += Duration::from_secs(pause);
+            for _ in 0..50 {
+                while let Some(wait) = bucket.try_take(now) {
+                    now += wait;
+                }
+                taken.push(now - start);
+            }
+        }
+
+        for (i, at) in taken.iter().enumerate() {
+            let window = taken[i..]
+                .iter()
+                .take_while(|t| **t < *at + Duration::from_secs(1))
+                .count();
+            assert!(
+                window <= 10,
+                "{} requests within a second of {:?}",
+                window,
+                at
+            );
+        }
+    }
 }
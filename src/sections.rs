@@ -0,0 +1,332 @@
+use std::ops::Range;
+
+use crate::{normalize::html_text, regexes};
+
+/// What a heading of the Chinese section is about.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Heading {
+    /// `Etymology` or `Etymology N`.
+    Etymology(Option<usize>),
+    /// `Pronunciation` or `Pronunciation N`.
+    Pronunciation(Option<usize>),
+    /// A part of speech, or `Definitions` when the entry does not name one.
+    PartOfSpeech(String),
+    GlyphOrigin,
+    Other(String),
+}
+
+impl Heading {
+    /// Part of speech headings used by Chinese entries.
+    const PARTS_OF_SPEECH: &'static [&'static str] = &[
+        "Definitions",
+        "Noun",
+        "Proper noun",
+        "Verb",
+        "Adjective",
+        "Adverb",
+        "Pronoun",
+        "Numeral",
+        "Classifier",
+        "Particle",
+        "Preposition",
+        "Postposition",
+        "Conjunction",
+        "Interjection",
+        "Determiner",
+        "Affix",
+        "Prefix",
+        "Suffix",
+        "Idiom",
+        "Phrase",
+        "Proverb",
+        "Symbol",
+        "Syllable",
+        "Onomatopoeia",
+    ];
+
+    pub fn parse(title: &str) -> Self {
+        let numbered = |name: &str| {
+            let rest = title.strip_prefix(name)?.trim();
+            match rest {
+                "" => Some(None),
+                n => n.parse().ok().map(Some),
+            }
+        };
+
+        if let Some(n) = numbered("Etymology") {
+            Self::Etymology(n)
+        } else if let Some(n) = numbered("Pronunciation") {
+            Self::Pronunciation(n)
+        } else if title == "Glyph origin" {
+            Self::GlyphOrigin
+        } else if Self::PARTS_OF_SPEECH.contains(&title) {
+            Self::PartOfSpeech(title.to_string())
+        } else {
+            Self::Other(title.to_string())
+        }
+    }
+}
+
+/// A heading and everything up to the next heading of the same or a higher level.
+#[derive(Debug, Clone)]
+pub struct Section {
+    pub heading: Heading,
+    /// The heading as it is written, e.g. `Pronunciation 2`.
+    pub title: String,
+    /// 3 for `<h3>` or `===`.
+    pub level: usize,
+    /// From the start of the heading to the end of the section.
+    pub range: Range<usize>,
+    /// From the end of the heading to the end of the section.
+    pub body: Range<usize>,
+    pub parent: Option<usize>,
+    pub children: Vec<usize>,
+}
+
+/// The headings of a language section, e.g. `Etymology 1` > `Pronunciation` > `Noun`.
+///
+/// Sections are kept in document order and refer to each other by index.
+#[derive(Debug)]
+pub struct Tree<'a> {
+    text: &'a str,
+    sections: Vec<Section>,
+}
+
+impl<'a> Tree<'a> {
+    /// Parses the `<h3>` to `<h6>` headings of a Parsoid HTML section.
+    pub fn html(text: &'a str) -> Self {
+        let headings = regexes()
+            .html_heading
+            .captures_iter(text)
+            .filter_map(|caps| {
+                let whole = caps.get(0)?;
+                let level = caps[1].parse().ok()?;
+                Some((level, html_text(&caps[2]), whole.start()..whole.end()))
+            });
+        Self::new(text, headings)
+    }
+
+    /// Parses the `===` to `======` headings of a wikitext section.
+    pub fn wikitext(text: &'a str) -> Self {
+        let headings = regexes()
+            .wikitext_heading
+            .captures_iter(text)
+            .filter_map(|caps| {
+                let whole = caps.get(0)?;
+                let level = caps[1].len().min(caps[3].len());
+                Some((
+                    level,
+                    caps[2].trim().to_string(),
+                    whole.start()..whole.end(),
+                ))
+            });
+        Self::new(text, headings)
+    }
+
+    fn new(text: &'a str, headings: impl Iterator<Item = (usize, String, Range<usize>)>) -> Self {
+        let mut sections = Vec::<Section>::new();
+        // Indices of the sections that are still open, outermost first.
+        let mut open = Vec::<usize>::new();
+
+        for (level, title, heading) in headings {
+            while let Some(&last) = open.last() {
+                if sections[last].level < level {
+                    break;
+                }
+                sections[last].range.end = heading.start;
+                sections[last].body.end = heading.start;
+                open.pop();
+            }
+
+            let index = sections.len();
+            let parent = open.last().copied();
+            if let Some(parent) = parent {
+                sections[parent].children.push(index);
+            }
+            sections.push(Section {
+                heading: Heading::parse(&title),
+                title,
+                level,
+                range: heading.start..text.len(),
+                body: heading.end..text.len(),
+                parent,
+                children: vec![],
+            });
+            open.push(index);
+        }
+
+        Self { text, sections }
+    }
+
+    /// The text under the heading of `section`, including its subsections.
+    pub fn body(&self, section: &Section) -> &'a str {
+        &self.text[section.body.clone()]
+    }
+
+    /// The text under the heading of `section` up to its first subsection.
+    pub fn intro(&self, section: &Section) -> &'a str {
+        let end = section
+            .children
+            .first()
+            .map_or(section.body.end, |&i| self.sections[i].range.start);
+        &self.text[section.body.start..end]
+    }
+
+    /// The glyph origin and etymology sections in document order.
+    pub fn origins(&self) -> Vec<&Section> {
+        self.sections
+            .iter()
+            .filter(|s| matches!(s.heading, Heading::GlyphOrigin | Heading::Etymology(_)))
+            .collect()
+    }
+
+    /// The title of `section`, after the title of the etymology it is under if any, e.g.
+    /// `Etymology 2, Pronunciation`.
+    pub fn label(&self, section: &Section) -> String {
+        match section.parent.map(|i| &self.sections[i]) {
+            Some(parent) if matches!(parent.heading, Heading::Etymology(_)) => {
+                format!("{}, {}", parent.title, section.title)
+            }
+            _ => section.title.clone(),
+        }
+    }
+
+    /// Every pronunciation section in document order.
+    pub fn pronunciations(&self) -> Vec<&Section> {
+        self.sections
+            .iter()
+            .filter(|s| matches!(s.heading, Heading::Pronunciation(_)))
+            .collect()
+    }
+
+    pub fn etymologies(&self) -> Vec<&Section> {
+        self.sections
+            .iter()
+            .filter(|s| matches!(s.heading, Heading::Etymology(_)))
+            .collect()
+    }
+
+    /// The pronunciations that belong to `etymology`.
+    pub fn pronunciations_of(&self, etymology: &Section) -> Vec<&Section> {
+        self.descendants(etymology)
+            .into_iter()
+            .filter(|s| matches!(s.heading, Heading::Pronunciation(_)))
+            .collect()
+    }
+
+    /// The part of speech sections that belong to `pronunciation`.
+    ///
+    /// They are either nested under it (`Pronunciation 1` > `Definitions`) or follow it at the
+    /// same level (`Etymology 1` > `Pronunciation`, `Noun`) up to the next pronunciation.
+    pub fn definitions(&self, pronunciation: &Section) -> Vec<&Section> {
+        let nested = self
+            .descendants(pronunciation)
+            .into_iter()
+            .filter(|s| matches!(s.heading, Heading::PartOfSpeech(_)))
+            .collect::<Vec<_>>();
+        if !nested.is_empty() {
+            return nested;
+        }
+
+        let roots;
+        let siblings = match pronunciation.parent {
+            Some(parent) => &self.sections[parent].children,
+            None => {
+                roots = self.roots();
+                &roots
+            }
+        };
+        siblings
+            .iter()
+            .map(|&i| &self.sections[i])
+            .skip_while(|s| s.range != pronunciation.range)
+            .skip(1)
+            .take_while(|s| !matches!(s.heading, Heading::Pronunciation(_) | Heading::Etymology(_)))
+            .filter(|s| matches!(s.heading, Heading::PartOfSpeech(_)))
+            .collect()
+    }
+
+    fn roots(&self) -> Vec<usize> {
+        (0..self.sections.len())
+            .filter(|&i| self.sections[i].parent.is_none())
+            .collect()
+    }
+
+    fn descendants(&self, section: &Section) -> Vec<&Section> {
+        let mut found = vec![];
+        let mut stack = section.children.iter().rev().copied().collect::<Vec<_>>();
+        while let Some(i) = stack.pop() {
+            let child = &self.sections[i];
+            found.push(child);
+            stack.extend(child.children.iter().rev());
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{fixture, isolate_chinese_section};
+
+    fn titles(sections: Vec<&Section>) -> Vec<&str> {
+        sections.into_iter().map(|s| s.title.as_str()).collect()
+    }
+
+    #[test]
+    fn nests_pronunciations_in_etymologies() {
+        let page = fixture('行');
+        let tree = Tree::html(isolate_chinese_section(&page).expect("no Chinese"));
+
+        assert_eq!(
+            titles(tree.origins()),
+            ["Glyph origin", "Etymology 1", "Etymology 2"]
+        );
+        let pronunciations = tree.pronunciations();
+        assert_eq!(pronunciations.len(), 2);
+        assert_eq!(tree.label(pronunciations[0]), "Etymology 1, Pronunciation");
+        assert_eq!(tree.label(pronunciations[1]), "Etymology 2, Pronunciation");
+        assert_eq!(
+            titles(tree.definitions(pronunciations[0])),
+            ["Verb", "Adjective"]
+        );
+        assert_eq!(titles(tree.definitions(pronunciations[1])), ["Noun"]);
+
+        let etymologies = tree.etymologies();
+        assert_eq!(tree.pronunciations_of(etymologies[1]).len(), 1);
+        let intro = tree.intro(etymologies[0]);
+        assert!(intro.contains("Proto-Sino-Tibetan"));
+        assert!(!intro.contains("Middle Chinese"));
+    }
+
+    #[test]
+    fn finds_definitions_after_a_pronunciation() {
+        let page = fixture('字');
+        let tree = Tree::html(isolate_chinese_section(&page).expect("no Chinese"));
+        let pronunciations = tree.pronunciations();
+        assert_eq!(pronunciations.len(), 1);
+        assert_eq!(tree.label(pronunciations[0]), "Pronunciation");
+        assert_eq!(titles(tree.definitions(pronunciations[0])), ["Definitions"]);
+        assert!(tree.etymologies().is_empty());
+    }
+
+    #[test]
+    fn parses_wikitext_headings() {
+        let text = "\n===Etymology 1===\nText\n====Pronunciation 1====\n{{zh-pron}}\n\
+                    =====Verb=====\n# to walk\n====Pronunciation 2====\n=====Noun=====\n\
+                    # row\n===Etymology 2===\n";
+        let tree = Tree::wikitext(text);
+        let pronunciations = tree.pronunciations();
+        assert_eq!(
+            titles(pronunciations.clone()),
+            ["Pronunciation 1", "Pronunciation 2"]
+        );
+        assert_eq!(
+            tree.definitions(pronunciations[0])[0].heading,
+            Heading::PartOfSpeech("Verb".to_string())
+        );
+        assert_eq!(titles(tree.definitions(pronunciations[1])), ["Noun"]);
+        assert_eq!(tree.intro(tree.etymologies()[0]).trim(), "Text");
+        assert_eq!(tree.body(tree.etymologies()[1]), "\n");
+    }
+}
@@ -0,0 +1,346 @@
+use std::{fmt::Display, path::PathBuf, str::FromStr};
+
+use crate::{
+    definitions::{self, Definitions},
+    error::Result,
+    etymology, japanese, kaikki, korean, middle, modern,
+    normalize::{html_text, normalized},
+    old_bs, old_zh,
+    sections::Tree,
+    translingual,
+    utils::{get_redirects, has_chinese_section, isolate_chinese_section, isolate_section},
+    vietnamese, wikitext,
+};
+
+/// Where pages come from.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Source {
+    /// The Wiktionary REST API (through the page cache).
+    #[default]
+    Wiktionary,
+    /// A wiktextract (kaikki.org) JSONL dump.
+    Kaikki(PathBuf),
+    /// Pages imported from a Wikimedia Enterprise HTML dump.
+    Dump(PathBuf),
+    /// A Kiwix ZIM archive of Wiktionary.
+    Zim(PathBuf),
+    /// Raw wikitext from Wiktionary.
+    Wikitext,
+}
+
+impl FromStr for Source {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (name, path) = s.split_once(':').unwrap_or((s, ""));
+        match (name, path) {
+            ("wiktionary", "") => Ok(Self::Wiktionary),
+            ("wikitext", "") => Ok(Self::Wikitext),
+            ("kaikki", "") => Err("expected `kaikki:<path>`".to_string()),
+            ("kaikki", path) => Ok(Self::Kaikki(path.into())),
+            ("dump", "") => Err("expected `dump:<dir>`".to_string()),
+            ("dump", path) => Ok(Self::Dump(path.into())),
+            ("zim", "") => Err("expected `zim:<path>`".to_string()),
+            ("zim", path) => Ok(Self::Zim(path.into())),
+            _ => Err(format!("unknown source `{}`", s)),
+        }
+    }
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Wiktionary => write!(f, "wiktionary"),
+            Self::Kaikki(path) => write!(f, "kaikki:{}", path.display()),
+            Self::Dump(path) => write!(f, "dump:{}", path.display()),
+            Self::Zim(path) => write!(f, "zim:{}", path.display()),
+            Self::Wikitext => write!(f, "wikitext"),
+        }
+    }
+}
+
+/// How a page that only points to another character is related to it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Relation {
+    #[default]
+    Simplified,
+    Variant,
+    Shinjitai,
+}
+
+impl Relation {
+    /// Reads the kind from a note such as `This character is a variant form of 裏`.
+    pub fn from_note(note: &str) -> Self {
+        let note = note.to_lowercase();
+        if note.contains("variant") {
+            Self::Variant
+        } else if note.contains("shinjitai") {
+            Self::Shinjitai
+        } else {
+            Self::Simplified
+        }
+    }
+}
+
+impl Display for Relation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Simplified => write!(f, "simplified form"),
+            Self::Variant => write!(f, "variant"),
+            Self::Shinjitai => write!(f, "Shinjitai form"),
+        }
+    }
+}
+
+/// A character a page sends the reader to with "For pronunciation and definitions of X – see Y".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redirect {
+    pub c: char,
+    /// The sense it covers, e.g. `hair` for 髮 from 发.
+    pub sense: Option<String>,
+    pub relation: Relation,
+}
+
+/// The entry of a character from any source.
+#[derive(Debug, Clone)]
+pub enum Page {
+    /// A Parsoid HTML page.
+    Html(String),
+    /// Records already extracted from a structured source.
+    Records(Box<kaikki::Entry>),
+    /// Raw wikitext with comments removed, with the data modules of its Middle and Old Chinese
+    /// tables.
+    Wikitext(String, wikitext::Modules),
+}
+
+impl Page {
+    /// The characters this page redirects to if it is a simplified form or a variant. 发 has
+    /// both 發 and 髮.
+    pub fn redirects(&self) -> Vec<Redirect> {
+        match self {
+            // NOTE: Shinjitai pages may only have a Japanese section pointing to the kyūjitai.
+            Self::Html(page) => get_redirects(isolate_chinese_section(page).unwrap_or(page)),
+            Self::Records(entry) => entry.redirects.clone(),
+            Self::Wikitext(text, _) => wikitext::chinese_section(text)
+                .map(wikitext::redirects)
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Whether the page has a Chinese entry and not only e.g. Japanese or Translingual ones.
+    pub fn has_chinese(&self) -> bool {
+        match self {
+            Self::Html(page) => has_chinese_section(page),
+            Self::Records(_) => true,
+            Self::Wikitext(text, _) => wikitext::chinese_section(text).is_some(),
+        }
+    }
+
+    /// The radical, strokes and input codes from the Translingual section. Empty if the page has
+    /// none.
+    pub fn translingual(&self) -> translingual::Data {
+        normalized(match self {
+            Self::Html(page) => isolate_section(page, "Translingual")
+                .map(translingual::fetch)
+                .unwrap_or_default(),
+            Self::Records(entry) => entry.translingual.clone(),
+            Self::Wikitext(text, _) => wikitext::language_section(text, "Translingual")
+                .map(translingual::from_wikitext)
+                .unwrap_or_default(),
+        })
+    }
+
+    /// The on'yomi and kun'yomi of the Japanese section. `None` if the page has no Japanese
+    /// entry.
+    pub fn japanese(&self) -> Option<japanese::Data> {
+        normalized(match self {
+            Self::Html(page) => isolate_section(page, "Japanese").ok().map(japanese::fetch),
+            Self::Records(entry) => entry.japanese.clone(),
+            Self::Wikitext(text, _) => {
+                wikitext::language_section(text, "Japanese").map(japanese::from_wikitext)
+            }
+        })
+    }
+
+    /// The Sino-Korean readings and eumhun of the Korean section. `None` if the page has no
+    /// Korean entry.
+    pub fn korean(&self) -> Option<korean::Data> {
+        normalized(match self {
+            Self::Html(page) => isolate_section(page, "Korean").ok().map(korean::fetch),
+            Self::Records(entry) => entry.korean.clone(),
+            Self::Wikitext(text, _) => {
+                wikitext::language_section(text, "Korean").map(korean::from_wikitext)
+            }
+        })
+    }
+
+    /// The Hán Việt and Nôm readings of the Vietnamese section. `None` if the page has no
+    /// Vietnamese entry.
+    pub fn vietnamese(&self) -> Option<vietnamese::Data> {
+        normalized(match self {
+            Self::Html(page) => isolate_section(page, "Vietnamese")
+                .ok()
+                .map(vietnamese::fetch),
+            Self::Records(entry) => entry.vietnamese.clone(),
+            Self::Wikitext(text, _) => {
+                wikitext::language_section(text, "Vietnamese").map(vietnamese::from_wikitext)
+            }
+        })
+    }
+
+    /// The glyph origin and every etymology section of the Chinese entry.
+    pub fn etymologies(&self) -> Result<Vec<etymology::Data>> {
+        Ok(normalized(match self {
+            Self::Html(page) => {
+                let tree = Tree::html(isolate_chinese_section(page)?);
+                tree.origins()
+                    .into_iter()
+                    .map(|s| etymology::from_text(&s.title, &html_text(tree.intro(s))))
+                    .collect()
+            }
+            Self::Records(entry) => entry.etymologies.clone(),
+            Self::Wikitext(text, _) => {
+                let tree = Tree::wikitext(wikitext::chinese_section(text).unwrap_or_default());
+                tree.origins()
+                    .into_iter()
+                    .map(|s| wikitext::etymology(&s.title, tree.intro(s)))
+                    .collect()
+            }
+        }))
+    }
+
+    pub fn pronunciations(&self) -> Result<Vec<Pronunciation<'_>>> {
+        Ok(match self {
+            Self::Html(page) => {
+                let tree = Tree::html(isolate_chinese_section(page)?);
+                tree.pronunciations()
+                    .into_iter()
+                    .map(|p| Pronunciation {
+                        label: tree.label(p),
+                        definitions: normalized(
+                            tree.definitions(p)
+                                .into_iter()
+                                .map(|pos| definitions::fetch(&pos.title, tree.body(pos)))
+                                .collect(),
+                        ),
+                        body: Body::Html(tree.body(p)),
+                    })
+                    .collect()
+            }
+            Self::Records(entry) => entry
+                .pronunciations
+                .iter()
+                .map(|records| Pronunciation {
+                    label: records.label.clone(),
+                    definitions: normalized(records.definitions.clone()),
+                    body: Body::Records(records),
+                })
+                .collect(),
+            Self::Wikitext(text, modules) => {
+                let tree = Tree::wikitext(wikitext::chinese_section(text).unwrap_or_default());
+                tree.pronunciations()
+                    .into_iter()
+                    .map(|p| Pronunciation {
+                        label: tree.label(p),
+                        definitions: normalized(
+                            tree.definitions(p)
+                                .into_iter()
+                                .map(|pos| wikitext::definitions(&pos.title, tree.body(pos)))
+                                .collect(),
+                        ),
+                        body: Body::Wikitext(tree.body(p), modules),
+                    })
+                    .collect()
+            }
+        })
+    }
+}
+
+/// A pronunciation section. Data is only extracted when asked for.
+#[derive(Debug, Clone)]
+pub struct Pronunciation<'a> {
+    /// The heading, after the etymology it is under if any, e.g. `Etymology 2, Pronunciation`.
+    pub label: String,
+    /// The parts of speech that go with this reading.
+    pub definitions: Vec<Definitions>,
+    body: Body<'a>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Body<'a> {
+    Html(&'a str),
+    Records(&'a kaikki::Pronunciation),
+    Wikitext(&'a str, &'a wikitext::Modules),
+}
+
+/// Every field is passed through [`normalized`], whatever the source.
+impl Pronunciation<'_> {
+    /// The top-level senses of every part of speech.
+    pub fn glosses(&self) -> impl Iterator<Item = &str> {
+        self.definitions
+            .iter()
+            .flat_map(|d| &d.senses)
+            .map(|s| s.gloss.as_str())
+    }
+
+    pub fn middle(&self) -> Result<Vec<middle::Data>> {
+        Ok(normalized(match self.body {
+            Body::Html(section) => middle::fetch(section)?,
+            Body::Records(records) => records.middle.clone(),
+            // NOTE: The Middle and Old Chinese tables are generated from data modules of the
+            // character. `{{zh-pron|mc=..|oc=..}}` only switches them on and picks the readings.
+            Body::Wikitext(section, modules) => wikitext::middle(section, modules)?,
+        }))
+    }
+
+    pub fn old_bs(&self) -> Result<Vec<old_bs::Data>> {
+        Ok(normalized(match self.body {
+            Body::Html(section) => old_bs::fetch(section)?,
+            Body::Records(records) => records.old_bs.clone(),
+            Body::Wikitext(section, modules) => wikitext::old_bs(section, modules),
+        }))
+    }
+
+    pub fn old_zh(&self) -> Result<Vec<old_zh::Data>> {
+        Ok(normalized(match self.body {
+            Body::Html(section) => old_zh::fetch(section)?,
+            Body::Records(records) => records.old_zh.clone(),
+            Body::Wikitext(section, modules) => wikitext::old_zh(section, modules),
+        }))
+    }
+
+    pub fn modern(&self) -> Result<modern::Data> {
+        Ok(normalized(match self.body {
+            Body::Html(section) => modern::fetch(section)?,
+            Body::Records(records) => records.modern.clone(),
+            Body::Wikitext(section, _) => wikitext::modern(section),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fixture;
+
+    #[test]
+    fn reads_every_redirect() {
+        let redirects = Page::Html(fixture('发')).redirects();
+        assert_eq!(
+            redirects,
+            [
+                Redirect {
+                    c: '發',
+                    sense: Some("to send out; to issue".to_string()),
+                    relation: Relation::Simplified,
+                },
+                Redirect {
+                    c: '髮',
+                    sense: Some("hair".to_string()),
+                    relation: Relation::Simplified,
+                },
+            ]
+        );
+        assert!(Page::Html(fixture('字')).redirects().is_empty());
+    }
+}
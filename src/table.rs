@@ -0,0 +1,106 @@
+use scraper::{ElementRef, Html, Node, Selector};
+use std::sync::OnceLock;
+
+use crate::error::{Error, Result};
+
+struct Selectors {
+    table: Selector,
+    tr: Selector,
+    th: Selector,
+    td: Selector,
+}
+
+fn selectors() -> &'static Selectors {
+    static SELECTORS: OnceLock<Selectors> = OnceLock::new();
+    SELECTORS.get_or_init(|| Selectors {
+        table: sel("table"),
+        tr: sel("tr"),
+        th: sel("th"),
+        td: sel("td"),
+    })
+}
+
+pub fn sel(selector: &str) -> Selector {
+    Selector::parse(selector).expect("invalid selector")
+}
+
+/// A table whose rows are labelled by their `<th>`, such as the Middle Chinese table of
+/// `{{zh-pron}}`. Each row maps to the `<td>` cells next to its label.
+pub struct Table<'a> {
+    /// Used in error messages.
+    name: &'static str,
+    rows: Vec<(String, Vec<ElementRef<'a>>)>,
+}
+
+impl<'a> Table<'a> {
+    /// Finds the first table in `doc` whose caption (the `<th>` of its first row) contains
+    /// `caption`.
+    pub fn find(doc: &'a Html, name: &'static str, caption: &str) -> Option<Self> {
+        doc.select(&selectors().table)
+            .find(|table| {
+                table
+                    .select(&selectors().th)
+                    .next()
+                    .is_some_and(|th| text(th).contains(caption))
+            })
+            .map(|table| Self::new(name, table))
+    }
+
+    fn new(name: &'static str, table: ElementRef<'a>) -> Self {
+        let rows = table
+            .select(&selectors().tr)
+            .filter_map(|tr| {
+                let label = text(tr.select(&selectors().th).next()?);
+                let cells = tr.select(&selectors().td).collect::<Vec<_>>();
+                Some((label, cells))
+            })
+            .collect();
+        Self { name, rows }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns the cells of the first row whose label starts with `label`.
+    pub fn row(&self, label: &str) -> Vec<ElementRef<'a>> {
+        self.rows
+            .iter()
+            .find(|(l, _)| l.starts_with(label))
+            .map(|(_, cells)| cells.clone())
+            .unwrap_or_default()
+    }
+
+    /// Returns the text of the cells of the first row whose label starts with `label`.
+    pub fn row_text(&self, label: &str) -> Vec<String> {
+        self.row(label).into_iter().map(text).collect()
+    }
+
+    /// Like [`Table::row`], but fails unless the row has one cell per reading.
+    pub fn row_exact(&self, label: &'static str, len: usize) -> Result<Vec<ElementRef<'a>>> {
+        let cells = self.row(label);
+        if cells.len() != len {
+            let value = cells.into_iter().map(text).collect::<Vec<_>>().join(" | ");
+            return Err(Error::malformed(self.name, label, value));
+        }
+        Ok(cells)
+    }
+
+    /// Like [`Table::row_text`], but fails unless the row has one cell per reading.
+    pub fn row_text_exact(&self, label: &'static str, len: usize) -> Result<Vec<String>> {
+        Ok(self.row_exact(label, len)?.into_iter().map(text).collect())
+    }
+}
+
+/// Returns the text of `elem` with `<br>` and runs of whitespace collapsed into one space.
+pub fn text(elem: ElementRef) -> String {
+    let mut raw = String::new();
+    for node in elem.descendants() {
+        match node.value() {
+            Node::Text(t) => raw.push_str(t),
+            Node::Element(e) if e.name() == "br" => raw.push(' '),
+            _ => {}
+        }
+    }
+    raw.split_whitespace().collect::<Vec<_>>().join(" ")
+}
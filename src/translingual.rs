@@ -0,0 +1,117 @@
+use crate::{
+    utils::{param, template_params},
+    wikitext,
+};
+
+/// What the `{{Han char}}` template of the Translingual section says about a character.
+#[derive(Debug, Clone, Default)]
+pub struct Data {
+    /// The Kangxi radical, e.g. `子`.
+    pub radical: Option<String>,
+    /// The number of the radical, from 1 to 214.
+    pub radical_number: Option<String>,
+    /// Strokes besides the radical.
+    pub additional_strokes: Option<String>,
+    pub strokes: Option<String>,
+    /// Ideographic description sequence, e.g. `⿱宀子`.
+    pub ids: Option<String>,
+    pub cangjie: Option<String>,
+    pub four_corner: Option<String>,
+}
+
+impl Data {
+    pub fn is_empty(&self) -> bool {
+        [
+            &self.radical,
+            &self.radical_number,
+            &self.additional_strokes,
+            &self.strokes,
+            &self.ids,
+            &self.cangjie,
+            &self.four_corner,
+        ]
+        .iter()
+        .all(|field| field.is_none())
+    }
+}
+
+/// Reads the parameters of `{{Han char}}` from the `data-mw` of a Parsoid HTML section.
+pub fn fetch(section: &str) -> Data {
+    match template_params(section, "Han char") {
+        Some(params) => from_params(|name| param(&params, name)),
+        None => Data::default(),
+    }
+}
+
+/// Reads `{{Han char}}` from the Translingual section of a wikitext page.
+pub fn from_wikitext(section: &str) -> Data {
+    match wikitext::templates(section, "Han char").first() {
+        Some(han_char) => from_params(|name| han_char.get(name).map(str::to_string)),
+        None => Data::default(),
+    }
+}
+
+/// Builds the data from the parameters of `{{Han char}}`.
+pub fn from_params(get: impl Fn(&str) -> Option<String>) -> Data {
+    Data {
+        radical: get("rad"),
+        radical_number: get("rn"),
+        additional_strokes: get("as"),
+        strokes: get("sn"),
+        ids: get("ids"),
+        cangjie: get("canj"),
+        four_corner: get("four"),
+    }
+}
+
+/// The code point of `c` and the block it is in, e.g. `U+5B57 (CJK Unified Ideographs)`.
+pub fn unicode(c: char) -> String {
+    format!("U+{:04X} ({})", c as u32, block(c))
+}
+
+fn block(c: char) -> &'static str {
+    match c {
+        '\u{3000}'..='\u{303F}' => "CJK Symbols and Punctuation",
+        '\u{3400}'..='\u{4DBF}' => "CJK Unified Ideographs Extension A",
+        '\u{4E00}'..='\u{9FFF}' => "CJK Unified Ideographs",
+        '\u{F900}'..='\u{FAFF}' => "CJK Compatibility Ideographs",
+        '\u{20000}'..='\u{2A6DF}' => "CJK Unified Ideographs Extension B",
+        '\u{2A700}'..='\u{2B73F}' => "CJK Unified Ideographs Extension C",
+        '\u{2B740}'..='\u{2B81F}' => "CJK Unified Ideographs Extension D",
+        '\u{2B820}'..='\u{2CEAF}' => "CJK Unified Ideographs Extension E",
+        '\u{2CEB0}'..='\u{2EBEF}' => "CJK Unified Ideographs Extension F",
+        '\u{2EBF0}'..='\u{2EE5F}' => "CJK Unified Ideographs Extension I",
+        '\u{2F800}'..='\u{2FA1F}' => "CJK Compatibility Ideographs Supplement",
+        '\u{30000}'..='\u{3134F}' => "CJK Unified Ideographs Extension G",
+        '\u{31350}'..='\u{323AF}' => "CJK Unified Ideographs Extension H",
+        _ => "Unknown block",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{fixture, isolate_section};
+
+    #[test]
+    fn reads_han_char() {
+        let page = fixture('行');
+        let data = fetch(isolate_section(&page, "Translingual").expect("no Translingual"));
+        assert_eq!(data.radical.as_deref(), Some("行"));
+        assert_eq!(data.radical_number.as_deref(), Some("144"));
+        assert_eq!(data.additional_strokes.as_deref(), Some("00"));
+        assert_eq!(data.strokes.as_deref(), Some("6"));
+        assert_eq!(data.cangjie.as_deref(), Some("HONN"));
+        assert_eq!(data.four_corner.as_deref(), Some("21221"));
+        assert_eq!(data.ids.as_deref(), Some("⿰彳亍"));
+    }
+
+    #[test]
+    fn names_blocks() {
+        assert_eq!(unicode('行'), "U+884C (CJK Unified Ideographs)");
+        assert_eq!(block('㐀'), "CJK Unified Ideographs Extension A");
+        assert_eq!(block('\u{FA5B}'), "CJK Compatibility Ideographs");
+        assert_eq!(block('𠀀'), "CJK Unified Ideographs Extension B");
+        assert_eq!(block('a'), "Unknown block");
+    }
+}
@@ -1,134 +1,158 @@
-use crate::regexes;
-use regex::Regex;
+use serde_json::Value;
 
-pub fn regex_isolate_one<'a>(
-    section: &'a str,
-    re_start: &Regex,
-    re_end: &Regex,
-) -> Option<&'a str> {
-    let mut locs = re_start.capture_locations();
-    let mut section_locs = (0, 0);
-    if re_start.captures_read(&mut locs, section).is_some() {
-        if let Some(loc) = locs.get(0) {
-            section_locs.0 = loc.1;
-            if re_end.captures_read_at(&mut locs, section, loc.1).is_some() {
-                if let Some(loc) = locs.get(0) {
-                    section_locs.1 = loc.0;
-                }
-            } else {
-                unreachable!("Misformatted HTML: Section does not end.");
-            }
-        }
-    }
+use crate::{
+    error::{Error, Result},
+    normalize::decode_entities,
+    regexes,
+    source::{Redirect, Relation},
+};
 
-    let new_section = section
-        .get(section_locs.0..section_locs.1)
-        .expect("invalid substring");
-    if !new_section.is_empty() {
-        Some(new_section)
-    } else {
-        None
-    }
+/// Whether `c` is a Han character that may have a Wiktionary entry.
+pub fn is_han(c: char) -> bool {
+    matches!(c,
+        '\u{3007}'                     // 〇
+        | '\u{3400}'..='\u{4DBF}'     // Extension A
+        | '\u{4E00}'..='\u{9FFF}'     // Unified Ideographs
+        | '\u{F900}'..='\u{FAFF}'     // Compatibility Ideographs
+        | '\u{20000}'..='\u{2FA1F}'   // Extensions B to F, Compatibility Supplement
+        | '\u{30000}'..='\u{323AF}'   // Extensions G and H
+    )
 }
 
-pub fn fetch_row<'a>(section: &'a str, re_row_start: &Regex, re_row_elem: &Regex) -> Vec<&'a str> {
-    let re_row_end = &regexes().row_end;
-    let mut elems = Vec::<&str>::new();
-    if let Some(row) = regex_isolate_one(section, re_row_start, re_row_end) {
-        for (_, [reading]) in re_row_elem.captures_iter(row).map(|c| c.extract()) {
-            elems.push(reading);
-        }
+pub fn has_chinese_section(page: &str) -> bool {
+    has_section(page, "Chinese")
+}
 
-        if elems.is_empty() {
-            unreachable!("Misformatted HTML: Row should be filled.");
-        }
-    } else {
-        unreachable!("Misformatted HTML: Section should have the specified row");
-    }
+/// Whether the page has a `<h2>` section for `language`.
+pub fn has_section(page: &str, language: &str) -> bool {
+    regexes()
+        .isolate_chinese_section
+        .captures_iter(page)
+        .any(|caps| caps.get(1).is_some_and(|name| name.as_str() == language))
+}
 
-    elems
+pub fn isolate_chinese_section(page: &str) -> Result<&str> {
+    isolate_section(page, "Chinese")
 }
 
-pub fn isolate_chinese_section(page: &str) -> &str {
+/// The body of the `<h2>` section of `language`, up to the next language.
+pub fn isolate_section<'a>(page: &'a str, language: &'static str) -> Result<&'a str> {
     let re = &regexes().isolate_chinese_section;
     let mut locs = re.capture_locations();
-    let mut chinese_section_locs = (0, 0);
+    let mut section_locs = (0, 0);
     let mut offset = 0;
-    let mut found_chinese_section = false;
+    let mut found_section = false;
     while re.captures_read_at(&mut locs, page, offset).is_some() {
         if let (Some(loc), Some(name_loc)) = (locs.get(0), locs.get(1)) {
-            if found_chinese_section {
-                chinese_section_locs.1 = loc.0;
+            if found_section {
+                section_locs.1 = loc.0;
                 break;
             }
-            if let Some("Chinese") = page.get(name_loc.0..name_loc.1) {
-                found_chinese_section = true;
-                chinese_section_locs.0 = loc.1;
+            if page.get(name_loc.0..name_loc.1) == Some(language) {
+                found_section = true;
+                section_locs.0 = loc.1;
             }
             offset = loc.1;
         }
     }
-    if chinese_section_locs.1 < chinese_section_locs.0 {
-        chinese_section_locs.1 = page.len();
+    if section_locs.1 < section_locs.0 {
+        section_locs.1 = page.len();
     }
 
-    if !found_chinese_section {
-        panic!("no Chinese section found");
+    if !found_section {
+        return Err(Error::MissingSection(language));
     }
 
-    page.get(chinese_section_locs.0..chinese_section_locs.1)
-        .expect("invalid substring")
+    Ok(page
+        .get(section_locs.0..section_locs.1)
+        .expect("invalid substring"))
 }
 
-pub fn pronunciation_sections(section: &str) -> Vec<&str> {
-    let re = &regexes().pronunciation_sections;
-    let mut pron_sections = Vec::<&str>::new();
-    let mut offset = 0;
-    let mut section_locs = (0, 0);
-    let mut search_end = false;
-    let mut locs = re.capture_locations();
+/// Every character linked from a `– see X (“sense”) and Y (“sense”)` line.
+pub fn get_redirects(section: &str) -> Vec<Redirect> {
+    let mut redirects = vec![];
+    for caps in regexes().see_redirect.captures_iter(section) {
+        let tail = regexes().html_tag.replace_all(&caps[1], "");
+        push_see_targets(&mut redirects, &tail);
+    }
+    redirects
+}
 
-    while re.captures_read_at(&mut locs, section, offset).is_some() {
-        if let (Some(loc), Some(name_loc)) = (locs.get(0), locs.get(1)) {
-            let name = section
-                .get(name_loc.0..name_loc.1)
-                .expect("invalid substring");
-            if search_end {
-                section_locs.1 = loc.0;
-                pron_sections.push(
-                    section
-                        .get(section_locs.0..section_locs.1)
-                        .expect("invalid substring"),
-                );
-                search_end = false;
-            }
-            if name.starts_with("Pronunciation") {
-                search_end = true;
-                section_locs.0 = loc.1;
-            }
-            offset = loc.1;
+/// Adds the targets of the plain text after `– see` to `redirects`, skipping duplicates. A
+/// trailing `(This character is a variant form of Y)` sets the relation.
+pub fn push_see_targets(redirects: &mut Vec<Redirect>, tail: &str) {
+    let (targets, relation) = match regexes().see_relation.captures(tail) {
+        Some(caps) => (
+            &tail[..caps.get(0).map_or(0, |m| m.start())],
+            Relation::from_note(&caps[1]),
+        ),
+        None => (tail, Relation::default()),
+    };
+    for caps in regexes().see_target.captures_iter(targets) {
+        let Some(c) = caps[1].chars().next() else {
+            continue;
+        };
+        if redirects.iter().any(|r| r.c == c) {
+            continue;
         }
+        redirects.push(Redirect {
+            c,
+            sense: caps.get(2).map(|m| m.as_str().to_string()),
+            relation,
+        });
     }
-    if section_locs.1 < section_locs.0 {
-        section_locs.1 = section.len();
-        pron_sections.push(
-            section
-                .get(section_locs.0..section_locs.1)
-                .expect("invalid substring"),
-        );
-    }
-
-    pron_sections
 }
 
-pub fn get_trad(section: &str) -> Option<char> {
-    let re = &regexes().traditional_char;
-    // NOTE: Only captures one section.
-    if let Some(caps) = re.captures(section) {
-        if let Some(ch) = caps.get(1) {
-            return ch.as_str().chars().next();
+/// The parameters of the first `{{name}}` in the `data-mw` attributes of a Parsoid HTML section.
+pub fn template_params(section: &str, name: &str) -> Option<Value> {
+    for caps in regexes().template_data.captures_iter(section) {
+        // NOTE: Other templates of the section carry `data-mw` too, and not all of it is JSON
+        // this needs to understand.
+        let Ok(data) = serde_json::from_str::<Value>(&decode_entities(&caps[1])) else {
+            continue;
+        };
+        let Some(template) = data
+            .get("parts")
+            .and_then(|p| p.get(0))
+            .and_then(|p| p.get("template"))
+        else {
+            continue;
+        };
+        let target = template
+            .get("target")
+            .and_then(|t| t.get("wt"))
+            .and_then(Value::as_str)
+            .map(str::trim);
+        if target == Some(name) {
+            return template.get("params").cloned();
         }
     }
-
     None
 }
+
+/// The wikitext of the parameter `name` in the `data-mw` parameters of a template, if it is not
+/// empty.
+pub fn param(params: &Value, name: &str) -> Option<String> {
+    let s = params.get(name)?.get("wt")?.as_str()?;
+    (!s.is_empty()).then(|| s.to_string())
+}
+
+/// The fixture page of `c` in `tests/fixtures`.
+#[cfg(test)]
+pub fn fixture(c: char) -> String {
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    crate::request::read_fixture(&dir, c, "html")
+        .expect("missing fixture")
+        .body
+}
+
+/// The body of every pronunciation section of the fixture page of `c`.
+#[cfg(test)]
+pub fn fixture_pronunciations(c: char) -> Vec<String> {
+    let page = fixture(c);
+    let tree = crate::sections::Tree::html(isolate_chinese_section(&page).expect("no Chinese"));
+    tree.pronunciations()
+        .into_iter()
+        .map(|p| tree.body(p).to_string())
+        .collect()
+}
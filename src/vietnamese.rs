@@ -0,0 +1,191 @@
+use std::fmt::Display;
+
+use unicode_normalization::UnicodeNormalization;
+
+use crate::{
+    utils::{param, template_params},
+    wikitext,
+};
+
+/// The six tones of Vietnamese, named after their marks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Tone {
+    #[default]
+    Ngang,
+    Huyen,
+    Sac,
+    Hoi,
+    Nga,
+    Nang,
+}
+
+impl Tone {
+    /// Reads the tone from the marks of a syllable such as `tự`.
+    pub fn of(syllable: &str) -> Self {
+        for c in syllable.nfd() {
+            match c {
+                '\u{0300}' => return Self::Huyen,
+                '\u{0301}' => return Self::Sac,
+                '\u{0309}' => return Self::Hoi,
+                '\u{0303}' => return Self::Nga,
+                '\u{0323}' => return Self::Nang,
+                _ => {}
+            }
+        }
+        Self::Ngang
+    }
+}
+
+impl Display for Tone {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ngang => write!(f, "ngang"),
+            Self::Huyen => write!(f, "huyền"),
+            Self::Sac => write!(f, "sắc"),
+            Self::Hoi => write!(f, "hỏi"),
+            Self::Nga => write!(f, "ngã"),
+            Self::Nang => write!(f, "nặng"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Reading {
+    pub text: String,
+    pub tone: Tone,
+}
+
+/// The readings of `{{vi-readings}}` in the Vietnamese section.
+#[derive(Debug, Clone, Default)]
+pub struct Data {
+    pub han_viet: Vec<Reading>,
+    pub nom: Vec<Reading>,
+}
+
+impl Data {
+    pub fn is_empty(&self) -> bool {
+        self.han_viet.is_empty() && self.nom.is_empty()
+    }
+}
+
+/// Reads the parameters of `{{vi-readings}}` from the `data-mw` of a Parsoid HTML section.
+pub fn fetch(section: &str) -> Data {
+    if let Some(params) = template_params(section, "vi-readings") {
+        return from_params(|name| param(&params, name));
+    }
+    // NOTE: Characters that are only used in Hán Việt may have `{{vi-hantu|tự}}` instead.
+    match template_params(section, "vi-hantu") {
+        Some(params) => from_hantu(|name| param(&params, name)),
+        None => Data::default(),
+    }
+}
+
+/// Reads `{{vi-readings}}` or `{{vi-hantu}}` from the Vietnamese section of a wikitext page.
+pub fn from_wikitext(section: &str) -> Data {
+    if let Some(vi_readings) = wikitext::templates(section, "vi-readings").first() {
+        return from_params(|name| vi_readings.get(name).map(str::to_string));
+    }
+    match wikitext::templates(section, "vi-hantu").first() {
+        Some(hantu) => from_hantu(|name| hantu.get(name).map(str::to_string)),
+        None => Data::default(),
+    }
+}
+
+/// Builds the data from the parameters of `{{vi-readings}}`.
+pub fn from_params(get: impl Fn(&str) -> Option<String>) -> Data {
+    let readings = |name| get(name).map(|s| readings(&s)).unwrap_or_default();
+    Data {
+        han_viet: readings("hanviet"),
+        nom: readings("nom"),
+    }
+}
+
+/// Builds the data from the parameters of `{{vi-hantu}}`, which only has the Hán Việt reading.
+pub fn from_hantu(get: impl Fn(&str) -> Option<String>) -> Data {
+    Data {
+        han_viet: get("1").map(|s| readings(&s)).unwrap_or_default(),
+        nom: vec![],
+    }
+}
+
+/// Splits `chữ, tợ` into readings.
+fn readings(list: &str) -> Vec<Reading> {
+    let mut readings = Vec::<Reading>::new();
+    for text in list.split([',', ';']).map(wikitext::plain) {
+        if text.is_empty() || readings.iter().any(|r| r.text == text) {
+            continue;
+        }
+        readings.push(Reading {
+            tone: Tone::of(&text),
+            text,
+        });
+    }
+    readings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{fixture, isolate_section};
+
+    fn readings(data: &[Reading]) -> Vec<(&str, Tone)> {
+        data.iter().map(|r| (r.text.as_str(), r.tone)).collect()
+    }
+
+    #[test]
+    fn reads_tones() {
+        for (syllable, tone) in [
+            ("ma", Tone::Ngang),
+            ("mư", Tone::Ngang),
+            ("mà", Tone::Huyen),
+            ("má", Tone::Sac),
+            ("mố", Tone::Sac),
+            ("mả", Tone::Hoi),
+            ("mở", Tone::Hoi),
+            ("mã", Tone::Nga),
+            ("mỗ", Tone::Nga),
+            ("mạ", Tone::Nang),
+            ("mặ", Tone::Nang),
+            // Decomposed: o, circumflex, tilde.
+            ("mo\u{0302}\u{0303}", Tone::Nga),
+        ] {
+            assert_eq!(Tone::of(syllable), tone, "{}", syllable);
+        }
+    }
+
+    #[test]
+    fn reads_vi_readings() {
+        let page = fixture('行');
+        let data = fetch(isolate_section(&page, "Vietnamese").expect("no Vietnamese"));
+        assert_eq!(
+            readings(&data.han_viet),
+            [
+                ("hành", Tone::Huyen),
+                ("hạnh", Tone::Nang),
+                ("hàng", Tone::Huyen),
+                ("hãng", Tone::Nga),
+            ]
+        );
+        assert_eq!(
+            readings(&data.nom),
+            [
+                ("hàng", Tone::Huyen),
+                ("hãng", Tone::Nga),
+                ("hạng", Tone::Nang),
+                ("hành", Tone::Huyen),
+            ]
+        );
+    }
+
+    #[test]
+    fn falls_back_to_vi_hantu() {
+        let section = concat!(
+            r#"<span typeof="mw:Transclusion" data-mw='{"parts":[{"template":"#,
+            r#"{"target":{"wt":"vi-hantu","href":"./Template:vi-hantu"},"#,
+            r#""params":{"1":{"wt":"tự"}},"i":0}}]}'>字</span>"#,
+        );
+        let data = fetch(section);
+        assert_eq!(readings(&data.han_viet), [("tự", Tone::Nang)]);
+        assert!(data.nom.is_empty());
+    }
+}
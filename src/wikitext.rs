@@ -0,0 +1,810 @@
+use std::borrow::Cow;
+
+use crate::{
+    definitions::{Definitions, Sense},
+    error::{Error, Result},
+    etymology,
+    middle::{self, Tones},
+    modern,
+    normalize::normalize,
+    old_bs, old_zh, regexes,
+    source::{Redirect, Relation},
+};
+
+/// A template invocation such as `{{zh-pron|m=zì|c=zi6}}`.
+/// Positional parameters are named by their index starting from `1`.
+#[derive(Debug, Default)]
+pub struct Template {
+    pub name: String,
+    pub params: Vec<(String, String)>,
+}
+
+impl Template {
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+            .filter(|v| !v.is_empty())
+    }
+}
+
+pub fn strip_comments(text: &str) -> Cow<'_, str> {
+    regexes().wikitext_comment.replace_all(text, "")
+}
+
+pub fn chinese_section(text: &str) -> Option<&str> {
+    let start = regexes().wikitext_chinese_heading.find(text)?.end();
+    let end = regexes()
+        .wikitext_language_heading
+        .find_at(text, start)
+        .map(|m| m.start())
+        .unwrap_or(text.len());
+    text.get(start..end)
+}
+
+/// The body of the `==language==` section, up to the next language.
+pub fn language_section<'a>(text: &'a str, language: &str) -> Option<&'a str> {
+    let re = &regexes().wikitext_language_heading;
+    let start = re
+        .find_iter(text)
+        .find(|m| m.as_str().trim().trim_matches('=').trim() == language)?
+        .end();
+    let end = re
+        .find_at(text, start)
+        .map(|m| m.start())
+        .unwrap_or(text.len());
+    text.get(start..end)
+}
+
+/// The characters `{{zh-see|X|type|sense}}` lines point to.
+pub fn redirects(section: &str) -> Vec<Redirect> {
+    let mut redirects = Vec::<Redirect>::new();
+    for template in templates(section, "zh-see") {
+        let Some(c) = template.get("1").and_then(|t| t.chars().next()) else {
+            continue;
+        };
+        if redirects.iter().any(|r| r.c == c) {
+            continue;
+        }
+        let relation = match template.get("2") {
+            None | Some("s" | "ss") => Relation::Simplified,
+            Some("j" | "sj") => Relation::Shinjitai,
+            Some(_) => Relation::Variant,
+        };
+        redirects.push(Redirect {
+            c,
+            sense: template.get("3").map(str::to_string),
+            relation,
+        });
+    }
+    redirects
+}
+
+/// Finds every invocation of the template `name` in `text`.
+pub fn templates(text: &str, name: &str) -> Vec<Template> {
+    all_templates(text)
+        .into_iter()
+        .filter(|template| template.name == name)
+        .collect()
+}
+
+/// Finds every template invocation in `text`, including nested ones.
+pub fn all_templates(text: &str) -> Vec<Template> {
+    let mut templates = vec![];
+    let mut offset = 0;
+    while let Some(found) = text[offset..].find("{{") {
+        let start = offset + found;
+        let Some(end) = closing_braces(text, start) else {
+            break;
+        };
+        templates.push(parse_template(&text[start + 2..end - 2]));
+        offset = start + 2;
+    }
+    templates
+}
+
+/// Returns the index after the `}}` that closes the `{{` at `start`.
+fn closing_braces(text: &str, start: usize) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let mut depth = 0;
+    let mut i = start;
+    while i + 1 < bytes.len() {
+        match &bytes[i..i + 2] {
+            b"{{" | b"[[" => {
+                depth += 1;
+                i += 2;
+            }
+            b"}}" | b"]]" => {
+                depth -= 1;
+                i += 2;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Parses the inside of `{{...}}`, splitting only at `|` that are not in nested templates or
+/// links.
+fn parse_template(inner: &str) -> Template {
+    let mut parts = vec![];
+    let mut depth = 0;
+    let mut last = 0;
+    let bytes = inner.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes.get(i..i + 2) {
+            Some(b"{{") | Some(b"[[") => {
+                depth += 1;
+                i += 2;
+                continue;
+            }
+            Some(b"}}") | Some(b"]]") => {
+                depth -= 1;
+                i += 2;
+                continue;
+            }
+            _ => {}
+        }
+        if bytes[i] == b'|' && depth == 0 {
+            parts.push(&inner[last..i]);
+            last = i + 1;
+        }
+        i += 1;
+    }
+    parts.push(&inner[last..]);
+
+    let mut parts = parts.into_iter();
+    let name = parts.next().unwrap_or_default().trim().to_string();
+    let mut index = 0;
+    let params = parts
+        .map(|part| match part.split_once('=') {
+            Some((key, value)) if !key.contains("{{") => {
+                (key.trim().to_string(), value.trim().to_string())
+            }
+            _ => {
+                index += 1;
+                (index.to_string(), part.trim().to_string())
+            }
+        })
+        .collect();
+
+    Template { name, params }
+}
+
+/// Reads the `# ...` senses of a part of speech section, `## ...` being sub-senses. Examples
+/// (`#:`) and quotations (`#*`) are left out.
+pub fn definitions(pos: &str, section: &str) -> Definitions {
+    let mut senses = Vec::<Sense>::new();
+    for line in section.lines() {
+        let rest = line.trim_start_matches('#');
+        let depth = line.len() - rest.len();
+        if depth == 0 || rest.starts_with(['*', ':']) {
+            continue;
+        }
+
+        let sense = Sense {
+            gloss: plain(rest),
+            labels: ["lb", "lbl", "label"]
+                .into_iter()
+                .flat_map(|name| templates(rest, name))
+                .flat_map(|template| template.params)
+                .filter(|(key, value)| {
+                    key.parse::<usize>().is_ok_and(|i| i >= 2)
+                        && !matches!(value.as_str(), "" | "_" | "and" | "or" | "&")
+                })
+                .map(|(_, value)| value)
+                .collect(),
+            senses: vec![],
+        };
+        if sense.gloss.is_empty() {
+            continue;
+        }
+
+        // NOTE: A sub-sense without a parent is kept at the deepest level there is.
+        let mut parent = &mut senses;
+        for _ in 1..depth {
+            if parent.is_empty() {
+                break;
+            }
+            parent = &mut parent.last_mut().expect("not empty").senses;
+        }
+        parent.push(sense);
+    }
+    Definitions {
+        pos: pos.to_string(),
+        senses,
+    }
+}
+
+/// Reads an etymology or glyph origin section from its text and templates.
+pub fn etymology(title: &str, section: &str) -> etymology::Data {
+    let mut data = etymology::from_text(title, &plain(section));
+    for template in all_templates(section) {
+        etymology::add_template(&mut data, &template.name, |name| {
+            template.get(name).map(str::to_string)
+        });
+    }
+    data
+}
+
+/// Drops templates and keeps the text of links, so
+/// `{{lb|zh|literary}} [[letter]]; [[symbol|symbols]]` becomes `letter; symbols`.
+pub fn plain(text: &str) -> String {
+    let mut plain = String::new();
+    let mut offset = 0;
+    while let Some(found) = text[offset..].find(['{', '[']) {
+        let start = offset + found;
+        plain.push_str(&text[offset..start]);
+        let rest = &text[start..];
+        let end = if rest.starts_with("{{") || rest.starts_with("[[") {
+            closing_braces(text, start)
+        } else {
+            None
+        };
+        let Some(end) = end else {
+            plain.push_str(&rest[..1]);
+            offset = start + 1;
+            continue;
+        };
+        let inner = &text[start + 2..end - 2];
+        if rest.starts_with("[[") {
+            plain.push_str(inner.rsplit('|').next().unwrap_or(inner));
+        } else if let Some(shown) = link_text(&parse_template(inner)) {
+            plain.push_str(&self::plain(&shown));
+        }
+        offset = end;
+    }
+    plain.push_str(&text[offset..]);
+    let plain = regexes().wikitext_tag.replace_all(&plain, "");
+    normalize(&plain.replace("'''", "").replace("''", ""))
+}
+
+/// The text shown by link templates such as `{{l|en|move}}` or `{{zh-l|字/字}}`.
+fn link_text(template: &Template) -> Option<String> {
+    let get = |name: &str| template.get(name).map(str::to_string);
+    match template.name.as_str() {
+        "l" | "ll" | "m" | "l-self" => get("3").or_else(|| get("2")),
+        "zh-l" | "zh-m" => get("1").map(|t| t.split('/').next().unwrap_or_default().to_string()),
+        "w" => get("2").or_else(|| get("1")),
+        "gloss" | "q" | "qual" | "i" => get("1").map(|t| format!("({})", t)),
+        // `{{inh|zh|sit-pro|*s-ŋa}}`, `{{cog|sit-pro|*s-ŋa}}`
+        "inh" | "inh+" | "der" | "der+" | "bor" | "bor+" | "cog" => {
+            let offset = if template.name == "cog" { 0 } else { 1 };
+            let lang = get(&(offset + 1).to_string())?;
+            let form = get(&(offset + 2).to_string()).unwrap_or_default();
+            let lang = match lang.as_str() {
+                "sit-pro" => "Proto-Sino-Tibetan",
+                "och" => "Old Chinese",
+                "ltc" => "Middle Chinese",
+                lang => lang,
+            };
+            Some(format!("{} {}", lang, form))
+        }
+        _ => None,
+    }
+}
+
+pub fn modern(section: &str) -> modern::Data {
+    match templates(section, "zh-pron").first() {
+        Some(zh_pron) => modern::from_params(|name| zh_pron.get(name).map(str::to_string)),
+        None => modern::Data::default(),
+    }
+}
+
+/// A data module the Middle and Old Chinese tables of a character are generated from. Its page
+/// is the prefix followed by the character, e.g. `Module:zh/data/ltc-pron/字`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Module {
+    /// The Qieyun initial, final, tone, openness, division and fanqie of each reading.
+    Ltc,
+    /// Baxter's Middle Chinese and Baxter–Sagart's Old Chinese of each reading.
+    OchBs,
+    /// Zhengzhang's Old Chinese of each reading.
+    OchZs,
+}
+
+impl Module {
+    pub const ALL: [Self; 3] = [Self::Ltc, Self::OchBs, Self::OchZs];
+
+    pub fn prefix(self) -> &'static str {
+        match self {
+            Self::Ltc => "Module:zh/data/ltc-pron/",
+            Self::OchBs => "Module:zh/data/och-pron-BS/",
+            Self::OchZs => "Module:zh/data/och-pron-ZS/",
+        }
+    }
+
+    /// Module pages are kept next to the page of the character in fixtures, so each has its own
+    /// extension.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Ltc => "ltc-pron.wikitext",
+            Self::OchBs => "och-pron-BS.wikitext",
+            Self::OchZs => "och-pron-ZS.wikitext",
+        }
+    }
+}
+
+/// The data modules of a character that were fetched. A module that was not asked for or has no
+/// page is `None`.
+#[derive(Debug, Clone, Default)]
+pub struct Modules {
+    pub ltc: Option<String>,
+    pub och_bs: Option<String>,
+    pub och_zs: Option<String>,
+}
+
+impl Modules {
+    pub fn get_mut(&mut self, module: Module) -> &mut Option<String> {
+        match module {
+            Module::Ltc => &mut self.ltc,
+            Module::OchBs => &mut self.och_bs,
+            Module::OchZs => &mut self.och_zs,
+        }
+    }
+}
+
+/// The data modules the `{{zh-pron}}` of a Chinese section switches tables on for. The
+/// Baxter–Sagart module is also needed for Middle Chinese, as it has Baxter's transcription.
+pub fn modules(section: &str) -> Vec<Module> {
+    let zh_prons = templates(section, "zh-pron");
+    let has = |name: &str| zh_prons.iter().any(|t| t.get(name).is_some());
+    let mut modules = vec![];
+    if has("mc") {
+        modules.push(Module::Ltc);
+    }
+    if has("mc") || has("oc") {
+        modules.push(Module::OchBs);
+    }
+    if has("oc") {
+        modules.push(Module::OchZs);
+    }
+    modules
+}
+
+/// Reads the Middle Chinese readings `{{zh-pron|mc=..}}` shows from the `ltc-pron` module. Each
+/// reading is `{initial, final, tone, openness, division, fanqie}`, optionally followed by
+/// Baxter's transcription. It is otherwise taken from the Baxter–Sagart module.
+pub fn middle(section: &str, modules: &Modules) -> Result<Vec<middle::Data>> {
+    let Some(param) = zh_pron_param(section, "mc") else {
+        return Ok(vec![]);
+    };
+    let readings = module_readings(modules.ltc.as_deref().unwrap_or_default());
+    let baxters = module_readings(modules.och_bs.as_deref().unwrap_or_default());
+
+    let mut datas = vec![];
+    for i in selected(&param, readings.len()) {
+        let field = |n: usize| readings[i].get(n).map(String::as_str).unwrap_or_default();
+        let tone = match field(2) {
+            "平" | "1" => Tones::Ping,
+            "上" | "2" => Tones::Shang,
+            "去" | "3" => Tones::Qu,
+            "入" | "4" => Tones::Ru,
+            tone => return Err(Error::malformed("Middle Chinese", "tone", tone)),
+        };
+        let open = match field(3) {
+            "開" | "开" => true,
+            "合" => false,
+            open => return Err(Error::malformed("Middle Chinese", "openness", open)),
+        };
+        let division = match field(4) {
+            "一" | "1" => 1,
+            "二" | "2" => 2,
+            "三" | "3" => 3,
+            "四" | "4" => 4,
+            division => return Err(Error::malformed("Middle Chinese", "division", division)),
+        };
+        let mut fanqie = field(5).to_string();
+        if !fanqie.is_empty() && !fanqie.ends_with('切') {
+            fanqie.push('切');
+        }
+        let baxter = Some(field(6))
+            .filter(|b| !b.is_empty())
+            .or_else(|| baxters.get(i)?.first().map(String::as_str))
+            .unwrap_or_default();
+        datas.push(middle::Data {
+            reading: format!("{}/{}", i + 1, readings.len()),
+            init: field(0).to_string(),
+            fin: field(1).to_string(),
+            tone,
+            open,
+            division,
+            fanqie,
+            baxter: baxter.to_string(),
+            ..middle::Data::default()
+        });
+    }
+    Ok(datas)
+}
+
+/// Reads the Baxter–Sagart readings the first part of `{{zh-pron|oc=..,..}}` picks. Each reading of the module is
+/// `{Middle Chinese, Old Chinese, gloss}`.
+pub fn old_bs(section: &str, modules: &Modules) -> Vec<old_bs::Data> {
+    old_chinese(section, modules.och_bs.as_deref(), 0, 1)
+        .into_iter()
+        .map(|(reading, old_chinese)| old_bs::Data {
+            reading,
+            old_chinese,
+        })
+        .collect()
+}
+
+/// Reads the Zhengzhang readings the second part of `{{zh-pron|oc=..,..}}` picks. Each reading of the module is
+/// `{phonetic component, rhyme group, subdivision, Middle Chinese rime, Old Chinese, notes}`.
+pub fn old_zh(section: &str, modules: &Modules) -> Vec<old_zh::Data> {
+    old_chinese(section, modules.och_zs.as_deref(), 1, 4)
+        .into_iter()
+        .map(|(reading, old_chinese)| old_zh::Data {
+            reading,
+            old_chinese,
+        })
+        .collect()
+}
+
+/// The reading number and the Old Chinese field `at` of the readings of a module that part
+/// `system` of `oc=` picks. A single part, e.g. `oc=y`, is for both systems.
+fn old_chinese(
+    section: &str,
+    module: Option<&str>,
+    system: usize,
+    at: usize,
+) -> Vec<(String, String)> {
+    let Some(param) = zh_pron_param(section, "oc") else {
+        return vec![];
+    };
+    let param = param
+        .split(',')
+        .nth(system)
+        .unwrap_or_else(|| param.split(',').next().unwrap_or_default());
+    let readings = module_readings(module.unwrap_or_default());
+    selected(param, readings.len())
+        .into_iter()
+        .map(|i| {
+            let old_chinese = readings[i].get(at).map(String::as_str).unwrap_or_default();
+            (
+                format!("{}/{}", i + 1, readings.len()),
+                old_chinese.trim_matches('/').to_string(),
+            )
+        })
+        .collect()
+}
+
+fn zh_pron_param(section: &str, name: &str) -> Option<String> {
+    templates(section, "zh-pron")
+        .first()?
+        .get(name)
+        .map(str::to_string)
+}
+
+/// The indices of the readings a `mc=`/`oc=` parameter picks out of `count`: the comma-separated
+/// numbers it lists, or all of them for anything else such as `y`.
+fn selected(param: &str, count: usize) -> Vec<usize> {
+    let numbers = param
+        .split(',')
+        .map(|n| n.trim().parse::<usize>())
+        .collect::<std::result::Result<Vec<_>, _>>();
+    match numbers {
+        Ok(numbers) => numbers
+            .into_iter()
+            .filter(|n| (1..=count).contains(n))
+            .map(|n| n - 1)
+            .collect(),
+        Err(_) => (0..count).collect(),
+    }
+}
+
+/// A value of a Lua table constructor. Numbers, booleans and `nil` are kept as they are written.
+#[derive(Debug, PartialEq)]
+enum Lua {
+    Value(String),
+    Table(Vec<Lua>),
+}
+
+/// Reads the positional fields of each reading of a data module, `return { {"從", "之", ..}, .. }`.
+/// A module returning a single reading, `return {"從", "之", ..}`, has one. Keyed fields are left
+/// out and nested tables are read as empty fields.
+fn module_readings(text: &str) -> Vec<Vec<String>> {
+    let Some(start) = text.find("return") else {
+        return vec![];
+    };
+    let mut reader = LuaReader {
+        rest: &text[start + "return".len()..],
+    };
+    let Some(Lua::Table(items)) = reader.value() else {
+        return vec![];
+    };
+    let fields = |items: Vec<Lua>| {
+        items
+            .into_iter()
+            .map(|item| match item {
+                Lua::Value(value) => value,
+                Lua::Table(_) => String::new(),
+            })
+            .collect::<Vec<_>>()
+    };
+    if !items.is_empty() && items.iter().all(|item| matches!(item, Lua::Value(_))) {
+        return vec![fields(items)];
+    }
+    items
+        .into_iter()
+        .filter_map(|item| match item {
+            Lua::Table(reading) => Some(fields(reading)),
+            Lua::Value(_) => None,
+        })
+        .collect()
+}
+
+struct LuaReader<'a> {
+    rest: &'a str,
+}
+
+impl LuaReader<'_> {
+    /// Skips whitespace and `--` comments, including `--[[ ]]` blocks.
+    fn skip(&mut self) {
+        loop {
+            self.rest = self.rest.trim_start();
+            let Some(comment) = self.rest.strip_prefix("--") else {
+                break;
+            };
+            self.rest = match comment.strip_prefix("[[") {
+                Some(block) => block.split_once("]]").map_or("", |(_, rest)| rest),
+                None => comment.split_once('\n').map_or("", |(_, rest)| rest),
+            };
+        }
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip();
+        match self.rest.strip_prefix(c) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn value(&mut self) -> Option<Lua> {
+        self.skip();
+        if let Some(rest) = self.rest.strip_prefix("[[") {
+            let (value, rest) = rest.split_once("]]")?;
+            self.rest = rest;
+            return Some(Lua::Value(value.to_string()));
+        }
+        let mut chars = self.rest.chars();
+        match chars.next()? {
+            '{' => {
+                self.rest = chars.as_str();
+                self.table()
+            }
+            quote @ ('"' | '\'') => {
+                let mut value = String::new();
+                loop {
+                    match chars.next()? {
+                        c if c == quote => break,
+                        '\\' => value.push(match chars.next()? {
+                            'n' => '\n',
+                            't' => '\t',
+                            c => c,
+                        }),
+                        c => value.push(c),
+                    }
+                }
+                self.rest = chars.as_str();
+                Some(Lua::Value(value))
+            }
+            _ => {
+                let end = self
+                    .rest
+                    .find(|c: char| c.is_whitespace() || matches!(c, ',' | ';' | '=' | '}' | ']'))
+                    .unwrap_or(self.rest.len());
+                if end == 0 {
+                    return None;
+                }
+                let (value, rest) = self.rest.split_at(end);
+                self.rest = rest;
+                Some(Lua::Value(value.to_string()))
+            }
+        }
+    }
+
+    /// Reads the fields of a table after its `{`.
+    fn table(&mut self) -> Option<Lua> {
+        let mut items = vec![];
+        loop {
+            if self.eat('}') {
+                return Some(Lua::Table(items));
+            }
+            // NOTE: `[key] = value` and `key = value` fields are read and dropped.
+            self.skip();
+            if !self.rest.starts_with("[[") && self.eat('[') {
+                self.value()?;
+                if !(self.eat(']') && self.eat('=')) {
+                    return None;
+                }
+                self.value()?;
+            } else {
+                let item = self.value()?;
+                if self.eat('=') {
+                    self.value()?;
+                } else {
+                    items.push(item);
+                }
+            }
+            if !(self.eat(',') || self.eat(';')) {
+                return self.eat('}').then_some(Lua::Table(items));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_keeps_infixes() {
+        assert_eq!(
+            plain("{{lb|zh|obsolete}} OC <sup>1</sup>*[k]ˤ<r>uŋ-s<br />"),
+            "OC 1*[k]ˤ<r>uŋ-s"
+        );
+    }
+
+    #[test]
+    fn reads_etymologies() {
+        let section = concat!(
+            "{{Han compound|宀|子|c1=s|c2=p}}\n",
+            "From {{inh|zh|sit-pro|*s-ŋa}}. Old Chinese *[dz]ə-s.\n",
+        );
+        let data = etymology("Glyph origin", section);
+        assert_eq!(data.semantic, ["宀"]);
+        assert_eq!(data.phonetic, ["子"]);
+        assert_eq!(data.sino_tibetan, ["*s-ŋa"]);
+        assert_eq!(data.old_chinese, ["*[dz]ə-s"]);
+    }
+
+    #[test]
+    fn reads_templates() {
+        let text = concat!(
+            "{{zh-see|發|s|to [[send]] out}} and ",
+            "{{zh-pron\n|m={{zh-l|字}}, zì\n|c=zi6\n|cat=n\n|ma=}}",
+        );
+        let zh_see = templates(text, "zh-see");
+        assert_eq!(zh_see.len(), 1);
+        assert_eq!(zh_see[0].get("1"), Some("發"));
+        assert_eq!(zh_see[0].get("2"), Some("s"));
+        assert_eq!(zh_see[0].get("3"), Some("to [[send]] out"));
+
+        let zh_pron = &templates(text, "zh-pron")[0];
+        assert_eq!(zh_pron.get("m"), Some("{{zh-l|字}}, zì"));
+        assert_eq!(zh_pron.get("c"), Some("zi6"));
+        assert_eq!(zh_pron.get("ma"), None);
+        assert_eq!(zh_pron.get("1"), None);
+
+        let names = all_templates(text)
+            .into_iter()
+            .map(|t| t.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["zh-see", "zh-pron", "zh-l"]);
+    }
+
+    #[test]
+    fn splits_only_outer_pipes() {
+        let template = parse_template("lb|zh|{{w|Qieyun|the rime book}}|[[a|b]]|x=1=2");
+        assert_eq!(template.name, "lb");
+        assert_eq!(
+            template.params,
+            [
+                ("1".to_string(), "zh".to_string()),
+                ("2".to_string(), "{{w|Qieyun|the rime book}}".to_string()),
+                ("3".to_string(), "[[a|b]]".to_string()),
+                ("x".to_string(), "1=2".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_modern_readings() {
+        let data = modern("{{zh-pron|m=zì|c=zi6|h=pfs=sṳ;gd=sii4|mn=jī/lī|cat=n}}");
+        assert_eq!(data.ma_standard.as_deref(), Some("zì"));
+        assert_eq!(data.ca_guangzhou.as_deref(), Some("zi6"));
+        assert_eq!(data.ha_sixian.as_deref(), Some("sṳ"));
+        assert_eq!(data.ha_meixian.as_deref(), Some("sii4"));
+        assert!(data.mi_hokkien.is_some());
+        assert!(data.gan.is_none());
+        assert!(modern("no pronunciation").ma_standard.is_none());
+    }
+
+    #[test]
+    fn reads_lua_tables() {
+        let text = concat!(
+            "-- Generated data\n",
+            "return {\n",
+            "\t{ \"匣\", '庚', \"平\", --[[ open ]] \"開\", 2, \"戶庚\" },\n",
+            "\t{ \"a\\\"b\", [[long]], nested = { 1 }, [\"key\"] = 2; {}, 3 },\n",
+            "}\n",
+        );
+        assert_eq!(
+            module_readings(text),
+            [
+                vec!["匣", "庚", "平", "開", "2", "戶庚"],
+                vec!["a\"b", "long", "", "3"],
+            ]
+        );
+        assert_eq!(
+            module_readings("return {\"dziH\", \"*[dz]ə-s\"}"),
+            [["dziH", "*[dz]ə-s"]]
+        );
+        assert!(module_readings("return {}").is_empty());
+        assert!(module_readings("not a module").is_empty());
+    }
+
+    #[test]
+    fn reads_middle_and_old_chinese_from_modules() {
+        let modules = Modules {
+            ltc: Some(
+                "return {{\"匣\",\"庚\",\"平\",\"開\",\"二\",\"戶庚\"},{\"匣\",\"唐\",\"平\",\"開\",\"一\",\"胡郎\"}}"
+                    .to_string(),
+            ),
+            och_bs: Some(
+                "return {{\"haeng\",\"/*[g]ˤraŋ/\",\"to walk\"},{\"hang\",\"*[g]ˤaŋ\",\"row\"}}"
+                    .to_string(),
+            ),
+            och_zs: Some(
+                "return {{\"行\",\"陽\",1,\"庚\",\"/*ɡraːŋ/\",\"\"},{\"行\",\"陽\",1,\"唐\",\"/*ɡaːŋ/\",\"\"}}"
+                    .to_string(),
+            ),
+        };
+
+        let section = "{{zh-pron|m=xíng|mc=y|oc=2}}";
+        let mc = middle(section, &modules).expect("malformed");
+        assert_eq!(mc.len(), 2);
+        assert_eq!(mc[0].reading, "1/2");
+        assert_eq!((mc[0].init.as_str(), mc[0].fin.as_str()), ("匣", "庚"));
+        assert_eq!(mc[0].tone.to_string(), "平");
+        assert!(mc[0].open);
+        assert_eq!(mc[0].division, 2);
+        assert_eq!(mc[0].fanqie, "戶庚切");
+        assert_eq!(mc[0].baxter, "haeng");
+        assert_eq!(mc[1].division, 1);
+        assert_eq!(mc[1].baxter, "hang");
+        assert_eq!(
+            mc[0].missing(),
+            ["expected Mandarin reflex", "expected Cantonese reflex"]
+        );
+
+        let bs = old_bs(section, &modules);
+        assert_eq!(bs.len(), 1);
+        assert_eq!(bs[0].reading, "2/2");
+        assert_eq!(bs[0].old_chinese, "*[g]ˤaŋ");
+        let zs = old_zh(section, &modules);
+        assert_eq!(zs.len(), 1);
+        assert_eq!(zs[0].old_chinese, "*ɡaːŋ");
+
+        assert_eq!(
+            super::modules(section),
+            [Module::Ltc, Module::OchBs, Module::OchZs]
+        );
+        let section = "{{zh-pron|m=xíng|oc=y,1}}";
+        assert_eq!(old_bs(section, &modules).len(), 2);
+        assert_eq!(old_zh(section, &modules)[0].reading, "1/2");
+        let section = "{{zh-pron|m=xíng}}";
+        assert!(middle(section, &modules).expect("malformed").is_empty());
+        assert!(old_bs(section, &modules).is_empty());
+        assert!(super::modules(section).is_empty());
+    }
+
+    #[test]
+    fn rejects_bad_module_fields() {
+        let modules = Modules {
+            ltc: Some("return {{\"匣\",\"庚\",\"平\",\"開\",\"五\",\"戶庚\"}}".to_string()),
+            ..Modules::default()
+        };
+        assert!(middle("{{zh-pron|mc=1}}", &modules).is_err());
+    }
+}
@@ -0,0 +1,383 @@
+use std::{
+    cmp::Ordering,
+    fs::File,
+    io::{self, BufReader, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+use crate::regexes;
+
+const MAGIC: u32 = 0x044D_495A;
+const REDIRECT: u16 = 0xffff;
+/// Redirects are followed at most this many times.
+const MAX_REDIRECTS: usize = 8;
+
+/// A Kiwix ZIM archive opened for reading.
+///
+/// Only what is needed to find an article by its URL and read its content is implemented.
+pub struct Zim {
+    file: File,
+    entry_count: u32,
+    url_ptr_pos: u64,
+    cluster_ptr_pos: u64,
+    cluster_count: u32,
+    checksum_pos: u64,
+    mime_types: Vec<String>,
+}
+
+enum Entry {
+    Content { mime: u16, cluster: u32, blob: u32 },
+    Redirect(u32),
+}
+
+impl Zim {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+        let mut header = [0; 80];
+        file.read_exact(&mut header)?;
+        if u32_at(&header, 0) != MAGIC {
+            return Err(invalid("not a ZIM file"));
+        }
+
+        let mut zim = Self {
+            file,
+            entry_count: u32_at(&header, 24),
+            cluster_count: u32_at(&header, 28),
+            url_ptr_pos: u64_at(&header, 32),
+            cluster_ptr_pos: u64_at(&header, 48),
+            checksum_pos: u64_at(&header, 72),
+            mime_types: vec![],
+        };
+        zim.mime_types = zim.read_mime_types(u64_at(&header, 56))?;
+
+        Ok(zim)
+    }
+
+    /// Returns the HTML of the article of `c`, adapted to the markup the extractors expect.
+    pub fn page(&mut self, c: char) -> io::Result<Option<String>> {
+        let url = c.to_string();
+        // NOTE: Archives since ZIM 6.1 keep articles in `C`, older ones in `A`.
+        let mut index = None;
+        for namespace in [b'C', b'A'] {
+            index = self.find(namespace, url.as_bytes())?;
+            if index.is_some() {
+                break;
+            }
+        }
+        let Some(mut index) = index else {
+            return Ok(None);
+        };
+
+        for _ in 0..MAX_REDIRECTS {
+            match self.entry(index)? {
+                Entry::Redirect(target) => index = target,
+                Entry::Content {
+                    mime,
+                    cluster,
+                    blob,
+                } => {
+                    if !self
+                        .mime_types
+                        .get(mime as usize)
+                        .is_some_and(|m| m.starts_with("text/html"))
+                    {
+                        return Ok(None);
+                    }
+                    let html = self.blob(cluster, blob)?;
+                    return Ok(Some(adapt(&String::from_utf8_lossy(&html))));
+                }
+            }
+        }
+
+        Err(invalid("too many redirects"))
+    }
+
+    /// Binary searches the URL pointer list, which is sorted by namespace then URL.
+    fn find(&mut self, namespace: u8, url: &[u8]) -> io::Result<Option<u32>> {
+        let (mut lo, mut hi) = (0, self.entry_count);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            let (ns, entry_url) = self.entry_key(mid)?;
+            match (ns, entry_url.as_slice()).cmp(&(namespace, url)) {
+                Ordering::Less => lo = mid + 1,
+                Ordering::Greater => hi = mid,
+                Ordering::Equal => return Ok(Some(mid)),
+            }
+        }
+        Ok(None)
+    }
+
+    fn entry_offset(&mut self, index: u32) -> io::Result<u64> {
+        self.read_u64(self.url_ptr_pos + 8 * index as u64)
+    }
+
+    fn entry_key(&mut self, index: u32) -> io::Result<(u8, Vec<u8>)> {
+        let offset = self.entry_offset(index)?;
+        let mut head = [0; 4];
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.read_exact(&mut head)?;
+        let mime = u16::from_le_bytes([head[0], head[1]]);
+        let namespace = head[3];
+        // Skip the revision and the cluster/blob or redirect index.
+        let skip = if mime == REDIRECT { 8 } else { 12 };
+        self.file.seek(SeekFrom::Current(skip))?;
+        let url = read_cstr(&mut BufReader::new(&mut self.file))?;
+        Ok((namespace, url))
+    }
+
+    fn entry(&mut self, index: u32) -> io::Result<Entry> {
+        let offset = self.entry_offset(index)?;
+        let mut head = [0; 16];
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.read_exact(&mut head)?;
+        let mime = u16::from_le_bytes([head[0], head[1]]);
+        Ok(if mime == REDIRECT {
+            Entry::Redirect(u32_at(&head, 8))
+        } else {
+            Entry::Content {
+                mime,
+                cluster: u32_at(&head, 8),
+                blob: u32_at(&head, 12),
+            }
+        })
+    }
+
+    fn blob(&mut self, cluster: u32, blob: u32) -> io::Result<Vec<u8>> {
+        if cluster >= self.cluster_count {
+            return Err(invalid("cluster out of range"));
+        }
+        let start = self.read_u64(self.cluster_ptr_pos + 8 * cluster as u64)?;
+        let end = if cluster + 1 < self.cluster_count {
+            self.read_u64(self.cluster_ptr_pos + 8 * (cluster as u64 + 1))?
+        } else {
+            self.checksum_pos
+        };
+
+        let mut raw = vec![0; end.saturating_sub(start) as usize];
+        self.file.seek(SeekFrom::Start(start))?;
+        self.file.read_exact(&mut raw)?;
+        let (info, compressed) = raw.split_first().ok_or_else(|| invalid("empty cluster"))?;
+
+        let data = match info & 0x0f {
+            0 | 1 => compressed.to_vec(),
+            4 => {
+                let mut data = vec![];
+                lzma_rs::xz_decompress(&mut &compressed[..], &mut data)
+                    .map_err(|e| invalid(&e.to_string()))?;
+                data
+            }
+            5 => zstd::stream::decode_all(compressed)?,
+            _ => return Err(invalid("unsupported cluster compression")),
+        };
+
+        let extended = info & 0x10 != 0;
+        let width = if extended { 8 } else { 4 };
+        let offset_at = |i: usize| -> Option<usize> {
+            let bytes = data.get(i * width..(i + 1) * width)?;
+            Some(if extended {
+                u64_at(bytes, 0) as usize
+            } else {
+                u32_at(bytes, 0) as usize
+            })
+        };
+        let blob = blob as usize;
+        match (offset_at(blob), offset_at(blob + 1)) {
+            (Some(start), Some(end)) if start <= end => data
+                .get(start..end)
+                .map(|b| b.to_vec())
+                .ok_or_else(|| invalid("blob out of range")),
+            _ => Err(invalid("blob out of range")),
+        }
+    }
+
+    fn read_mime_types(&mut self, pos: u64) -> io::Result<Vec<String>> {
+        self.file.seek(SeekFrom::Start(pos))?;
+        let mut reader = BufReader::new(&mut self.file);
+        let mut types = vec![];
+        loop {
+            let mime = read_cstr(&mut reader)?;
+            if mime.is_empty() {
+                break;
+            }
+            types.push(String::from_utf8_lossy(&mime).into_owned());
+        }
+        Ok(types)
+    }
+
+    fn read_u64(&mut self, pos: u64) -> io::Result<u64> {
+        let mut buf = [0; 8];
+        self.file.seek(SeekFrom::Start(pos))?;
+        self.file.read_exact(&mut buf)?;
+        Ok(u64::from_le_bytes(buf))
+    }
+}
+
+/// Rewrites the markup Kiwix (mwoffliner) produces into the Parsoid form the extractors match:
+/// `<h2><span class="mw-headline" id="X">X</span></h2>` and `<h2 class=".." id="X">` become
+/// `<h2 id="X">`, and plain `<a href="X#Chinese">` links become `rel="mw:WikiLink"` links.
+pub fn adapt(html: &str) -> String {
+    let html = regexes()
+        .zim_headline
+        .replace_all(html, r#"<h$1 id="$2">$3</h$1>"#);
+    let html = regexes().zim_heading.replace_all(&html, r#"<h$1 id="$2">"#);
+    regexes()
+        .zim_chinese_link
+        .replace_all(
+            &html,
+            r#"<a rel="mw:WikiLink" href="./$1#Chinese" title="$1">"#,
+        )
+        .into_owned()
+}
+
+fn read_cstr(reader: &mut impl Read) -> io::Result<Vec<u8>> {
+    let mut bytes = vec![];
+    let mut byte = [0];
+    loop {
+        reader.read_exact(&mut byte)?;
+        if byte[0] == 0 {
+            return Ok(bytes);
+        }
+        bytes.push(byte[0]);
+    }
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(bytes[at..at + 4].try_into().expect("slice of 4 bytes"))
+}
+
+fn u64_at(bytes: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(bytes[at..at + 8].try_into().expect("slice of 8 bytes"))
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, fs};
+
+    /// A cluster of `blobs`, with 8-byte offsets if `extended`, compressed with zstd if `zstd`.
+    fn cluster(blobs: &[&str], extended: bool, zstd: bool) -> Vec<u8> {
+        let width = if extended { 8 } else { 4 };
+        let mut offsets = vec![];
+        let mut offset = (blobs.len() + 1) * width;
+        for blob in blobs.iter().map(|b| b.len()).chain([0]) {
+            if extended {
+                offsets.extend((offset as u64).to_le_bytes());
+            } else {
+                offsets.extend((offset as u32).to_le_bytes());
+            }
+            offset += blob;
+        }
+        let mut data = offsets;
+        for blob in blobs {
+            data.extend(blob.as_bytes());
+        }
+
+        let info = if zstd { 5 } else { 1 } | if extended { 0x10 } else { 0 };
+        let data = if zstd {
+            zstd::stream::encode_all(&data[..], 0).expect("failed to compress")
+        } else {
+            data
+        };
+        [vec![info], data].concat()
+    }
+
+    /// A content entry, or a redirect to the entry at `redirect`.
+    fn dirent(url: char, mime: u16, cluster: u32, blob: u32, redirect: Option<u32>) -> Vec<u8> {
+        let mut dirent = vec![];
+        dirent.extend(redirect.map_or(mime, |_| REDIRECT).to_le_bytes());
+        dirent.extend([0, b'C']);
+        dirent.extend(0u32.to_le_bytes());
+        match redirect {
+            Some(target) => dirent.extend(target.to_le_bytes()),
+            None => {
+                dirent.extend(cluster.to_le_bytes());
+                dirent.extend(blob.to_le_bytes());
+            }
+        }
+        dirent.extend(url.to_string().as_bytes());
+        dirent.extend([0, 0]);
+        dirent
+    }
+
+    /// 字 in an uncompressed cluster, 行 in a zstd cluster with extended offsets, 髮 redirecting
+    /// to 行 and an image at 圖. Entries are sorted by URL.
+    fn archive() -> Vec<u8> {
+        let mimes = b"text/html\0image/png\0\0".to_vec();
+        let dirents = [
+            dirent('圖', 1, 0, 1, None),
+            dirent('字', 0, 0, 0, None),
+            dirent('行', 0, 1, 1, None),
+            dirent('髮', 0, 0, 0, Some(2)),
+        ];
+        let clusters = [
+            cluster(&["<p>字</p>", "png"], false, false),
+            cluster(
+                &[
+                    "unused",
+                    r#"<h2><span class="mw-headline" id="Chinese">Chinese</span></h2><p>行</p>"#,
+                ],
+                true,
+                true,
+            ),
+        ];
+
+        let mut pos = 80 + mimes.len() as u64;
+        let mut url_ptrs = vec![];
+        for dirent in &dirents {
+            url_ptrs.extend(pos.to_le_bytes());
+            pos += dirent.len() as u64;
+        }
+        let url_ptr_pos = pos;
+        let cluster_ptr_pos = url_ptr_pos + url_ptrs.len() as u64;
+        pos = cluster_ptr_pos + 8 * clusters.len() as u64;
+        let mut cluster_ptrs = vec![];
+        for cluster in &clusters {
+            cluster_ptrs.extend(pos.to_le_bytes());
+            pos += cluster.len() as u64;
+        }
+        let checksum_pos = pos;
+
+        let mut header = vec![0; 80];
+        header[0..4].copy_from_slice(&MAGIC.to_le_bytes());
+        header[24..28].copy_from_slice(&(dirents.len() as u32).to_le_bytes());
+        header[28..32].copy_from_slice(&(clusters.len() as u32).to_le_bytes());
+        header[32..40].copy_from_slice(&url_ptr_pos.to_le_bytes());
+        header[48..56].copy_from_slice(&cluster_ptr_pos.to_le_bytes());
+        header[56..64].copy_from_slice(&80u64.to_le_bytes());
+        header[72..80].copy_from_slice(&checksum_pos.to_le_bytes());
+
+        [
+            header,
+            mimes,
+            dirents.concat(),
+            url_ptrs,
+            cluster_ptrs,
+            clusters.concat(),
+            vec![0; 16],
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn reads_articles() {
+        let path = env::temp_dir().join(format!("wangyun-{}.zim", std::process::id()));
+        fs::write(&path, archive()).expect("failed to write");
+        let zim = Zim::open(&path);
+        let pages = zim.map(|mut zim| ['字', '行', '髮', '圖', '我'].map(|c| zim.page(c)));
+        fs::remove_file(&path).expect("failed to clean up");
+
+        let [zi, xing, fa, tu, wo] = pages
+            .expect("failed to open")
+            .map(|page| page.expect("failed to read"));
+        let chinese = r#"<h2 id="Chinese">Chinese</h2><p>行</p>"#;
+        assert_eq!(zi.as_deref(), Some("<p>字</p>"));
+        assert_eq!(xing.as_deref(), Some(chinese));
+        assert_eq!(fa.as_deref(), Some(chinese));
+        assert_eq!(tu, None);
+        assert_eq!(wo, None);
+    }
+}